@@ -1,22 +1,39 @@
+use bevy::ecs::query::QueryData;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::f32::consts::PI;
 use std::ops::{Add, Mul, Sub};
 
 use crate::audio::{play_sfx, Sfx, SfxSource};
+use crate::boss_movement::BossPose;
 use crate::boss_phase::{Puddle, PuddleSpawn};
+use crate::downed::{Downed, REVIVE_RANGE};
+use crate::fears::{fear_destination, Fear};
+use crate::frost_beams::FrostBeam;
 use crate::game::Player;
 use crate::greens::StackGreen;
 use crate::mobs::Enemy;
 use crate::orbs::ORB_RADIUS;
 use crate::{
-    collide, Aoe, AoeFollow, Boss, Bullet, CollisionRadius, EffectForcedMarch, Game, GameState,
-    HasHit, Hp, MobOrb, MobSaltspray, OrbTarget, PhaseEntity, Soup, StackGreenIndicator, Velocity,
-    VoidZone, Wave, BULLET_DAMAGE, BULLET_SIZE, BULLET_SPEED, DODGE_DURATION_S, GAME_TO_PX,
-    JUMP_DURATION_S, LAYER_BULLET, MAP_RADIUS, PLAYER_RADIUS, WAVE_MAX_RADIUS,
+    collide, Aoe, AoeFollow, AoeKnockback, Boss, Bullet, CollisionArea, CollisionRadius,
+    EffectForcedMarch, Game, GameState, HasHit, Hp, MobOrb, MobSaltspray, OrbTarget, PhaseEntity,
+    Soup, StackGreenIndicator, Velocity, VoidZone, Wave, BULLET_DAMAGE, BULLET_SIZE, BULLET_SPEED,
+    DODGE_DURATION_S, GAME_TO_PX, JUMP_DURATION_S, LAYER_BULLET, MAP_RADIUS, PLAYER_RADIUS,
+    WAVE_MAX_RADIUS,
 };
 
-#[derive(Copy, Clone, PartialEq)]
+const FROST_BEAM_LOOKAHEAD_S: f32 = 1.5;
+
+#[derive(QueryData)]
+pub struct AvoidableAoe {
+    aoe: &'static Aoe,
+    area: CollisionArea,
+    follow: Option<&'static AoeFollow>,
+    knockback: Option<&'static AoeKnockback>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum AiRole {
     Virt1,
     Virt2,
@@ -43,7 +60,20 @@ impl ToString for AiRole {
 }
 
 impl AiRole {
-    fn is_blink_enabled(&self) -> bool {
+    pub const ALL: [AiRole; 10] = [
+        AiRole::Virt1,
+        AiRole::Virt2,
+        AiRole::Herald1,
+        AiRole::Herald2,
+        AiRole::Ham1,
+        AiRole::Ham2,
+        AiRole::Dps1,
+        AiRole::Dps2,
+        AiRole::Dps3,
+        AiRole::Dps4,
+    ];
+
+    pub fn is_blink_enabled(&self) -> bool {
         match self {
             AiRole::Virt1 | AiRole::Virt2 | AiRole::Ham1 | AiRole::Ham2 => true,
             AiRole::Herald1
@@ -66,6 +96,7 @@ enum Action {
     Move(Vec3),
     Shoot(Vec3),
     Jump,
+    Revive,
     Rest,
 }
 
@@ -130,6 +161,48 @@ fn think_jump_wave(
     Thought::REST
 }
 
+fn think_revive(
+    player_pos: Vec3,
+    downed_players: &Query<&GlobalTransform, With<Downed>>,
+    soups: &Query<(&Soup, CollisionArea, Option<&FrostBeam>), Without<Player>>,
+) -> Thought {
+    let seek_range = MAP_RADIUS / 2.;
+
+    let closest_downed = downed_players
+        .iter()
+        .map(|transform| transform.translation())
+        .filter(|downed_pos| collide(player_pos, seek_range, *downed_pos, 0.))
+        // Don't stand in anything nasty to pick someone up
+        .filter(|downed_pos| {
+            !soups
+                .iter()
+                .any(|(soup, area, _)| soup.damage > 19. && area.collide(*downed_pos, REVIVE_RANGE))
+        })
+        .min_by(|a, b| {
+            let dist_a = a.sub(player_pos).length_squared();
+            let dist_b = b.sub(player_pos).length_squared();
+            dist_a.total_cmp(&dist_b)
+        });
+
+    let Some(downed_pos) = closest_downed else {
+        return Thought::REST;
+    };
+
+    // Lower than avoiding aoes so we only revive when it's safe
+    let utility = 0.6;
+    if collide(player_pos, REVIVE_RANGE - PLAYER_RADIUS, downed_pos, 0.) {
+        return Thought {
+            utility,
+            action: Action::Revive,
+        };
+    }
+
+    Thought {
+        utility,
+        action: Action::Move(downed_pos),
+    }
+}
+
 fn is_safe_for_orb(
     player_pos: Vec3,
     orb_pos: Vec3,
@@ -335,20 +408,30 @@ pub fn player_ai_boss_phase_system(
         (Entity, &mut Player, &AiPlayer, &mut Transform),
         Without<EffectForcedMarch>,
     >,
+    downed_players: Query<&GlobalTransform, With<Downed>>,
     enemies: Query<(&Enemy, &Transform, &Visibility, Option<&Boss>), Without<Player>>,
     greens: Query<(&StackGreen, &Children)>,
     indicators: Query<(&StackGreenIndicator, &Transform), Without<Player>>,
     puddle_spawns: Query<&PuddleSpawn>,
     puddles: Query<(&Puddle, &CollisionRadius, &Transform), Without<Player>>,
-    soups: Query<(&Soup, &Transform, &CollisionRadius), Without<Player>>,
-    aoes: Query<(&Aoe, &Transform, &CollisionRadius, Option<&AoeFollow>), Without<Player>>,
+    soups: Query<(&Soup, CollisionArea, Option<&FrostBeam>), Without<Player>>,
+    aoes: Query<AvoidableAoe, Without<Player>>,
     void_zones: Query<(&CollisionRadius, &Transform), (With<VoidZone>, Without<Player>)>,
     waves: Query<(&Wave, &Visibility, &Transform), Without<Player>>,
+    fears: Query<&Aoe, With<Fear>>,
 ) {
     let center_void_zone = void_zones.single();
     let (center_void_zone_radius, _) = center_void_zone;
+    let boss_pose = enemies
+        .iter()
+        .find_map(|(_, transform, _, boss)| boss.map(|_| BossPose::from_transform(transform)))
+        .unwrap_or_default();
 
     for (entity_player, mut player, ai_player, mut transform) in &mut players {
+        if downed_players.contains(entity_player) {
+            continue;
+        }
+
         let player_pos = transform.translation;
 
         let thoughts: Vec<Thought> = vec![
@@ -363,9 +446,17 @@ pub fn player_ai_boss_phase_system(
                 &puddles,
                 &void_zones,
             ),
-            think_go_home(game_state.get(), &ai_player.role, player_pos),
+            think_go_home(game_state.get(), &ai_player.role, &boss_pose, player_pos),
             think_avoid_aoes(entity_player, player_pos, &aoes),
             think_jump_wave((&player, &transform), &waves),
+            think_revive(player_pos, &downed_players, &soups),
+            think_prepare_for_fear(
+                player_pos,
+                center_void_zone_radius.0,
+                &fears,
+                &enemies,
+                &puddles,
+            ),
         ];
 
         act_on_thoughts(
@@ -531,33 +622,114 @@ fn think_do_puddles(
     Thought::REST
 }
 
+fn visible_aoe(aoe: &Aoe) -> bool {
+    if let Some(vis_start) = &aoe.visibility_start {
+        vis_start.finished()
+    } else {
+        true
+    }
+}
+
+/// Get close enough to the boss that running away from it ends up somewhere safe
+fn think_prepare_for_fear(
+    player_pos: Vec3,
+    center_void_zone_radius: f32,
+    fears: &Query<&Aoe, With<Fear>>,
+    enemies: &Query<(&Enemy, &Transform, &Visibility, Option<&Boss>), Without<Player>>,
+    puddles: &Query<(&Puddle, &CollisionRadius, &Transform), Without<Player>>,
+) -> Thought {
+    let fear_soon = fears.iter().any(|aoe| {
+        visible_aoe(aoe)
+            || aoe
+                .visibility_start
+                .as_ref()
+                .is_some_and(|vis_start| vis_start.remaining_secs() < 3.)
+    });
+    if !fear_soon {
+        return Thought::REST;
+    }
+
+    let Some(boss_pos) = enemies
+        .iter()
+        .find(|(_, _, _, boss)| boss.is_some())
+        .map(|(_, transform, _, _)| transform.translation)
+    else {
+        return Thought::REST;
+    };
+
+    let safe_map_radius = MAP_RADIUS - PLAYER_RADIUS * 2.;
+    let is_safe = |pos: Vec3| {
+        let dest = fear_destination(pos, boss_pos);
+        let dest_dist = dest.truncate().length();
+        dest_dist < safe_map_radius
+            && dest_dist > center_void_zone_radius + PLAYER_RADIUS
+            && !puddles.iter().any(|(_, radius, transform)| {
+                collide(dest, PLAYER_RADIUS, transform.translation, radius.0)
+            })
+    };
+
+    if is_safe(player_pos) {
+        return Thought::REST;
+    }
+
+    let to_boss = boss_pos
+        .sub(player_pos)
+        .truncate()
+        .normalize_or_zero()
+        .extend(0.);
+    let mut target_pos = player_pos;
+    for _ in 0..40 {
+        target_pos = target_pos.add(to_boss.mul(PLAYER_RADIUS));
+        if is_safe(target_pos) {
+            break;
+        }
+    }
+
+    Thought {
+        utility: 0.96,
+        action: Action::Move(target_pos),
+    }
+}
+
 fn think_avoid_aoes(
     player_entity: Entity,
     player_pos: Vec3,
-    aoes: &Query<(&Aoe, &Transform, &CollisionRadius, Option<&AoeFollow>), Without<Player>>,
+    aoes: &Query<AvoidableAoe, Without<Player>>,
 ) -> Thought {
     let mut avg_overlapping_aoe_pos = Vec3::ZERO;
     let mut n_overlapping = 0.;
 
-    for (aoe, transform, radius, aoe_follow) in aoes {
+    for AvoidableAoeItem {
+        aoe,
+        area,
+        follow: aoe_follow,
+        knockback,
+    } in aoes
+    {
         if let Some(aoe_follow) = aoe_follow {
             if aoe_follow.target == player_entity {
                 continue;
             }
         }
+        // The circle special cases below don't apply to shaped aoes
+        if area.shape.is_some() {
+            if !visible_aoe(aoe) || !area.collide(player_pos, PLAYER_RADIUS / 4.) {
+                continue;
+            }
+            avg_overlapping_aoe_pos = avg_overlapping_aoe_pos.add(area.spine_point(player_pos));
+            n_overlapping += 1.;
+            continue;
+        }
+
+        let (transform, radius) = (area.transform, area.radius);
+
         let big_and_about_to_happen = radius.0 > 300.
             && if let Some(vis_start) = &aoe.visibility_start {
                 vis_start.remaining_secs() < 3.
             } else {
                 false
             };
-        let visible = if let Some(vis_start) = &aoe.visibility_start {
-            vis_start.finished()
-        } else {
-            true
-        };
-
-        if !visible && !big_and_about_to_happen {
+        if !visible_aoe(aoe) && !big_and_about_to_happen {
             continue;
         }
 
@@ -571,12 +743,15 @@ fn think_avoid_aoes(
             continue;
         }
 
-        // Special-case primordus chomps
         let diff = aoe_pos.sub(player_pos);
         let target_pos = player_pos.add(diff.mul(-1.));
-        if radius.0 > MAP_RADIUS - PLAYER_RADIUS
-            || target_pos.length_squared() > MAP_RADIUS * MAP_RADIUS
-        {
+        let target_off_map = target_pos.length_squared() > MAP_RADIUS * MAP_RADIUS;
+        if knockback.is_some() && target_off_map {
+            // Running for the edge out of a knockback only gets you thrown
+            // off it, so head back towards the middle instead
+            aoe_pos = player_pos.add(player_pos.normalize_or_zero().mul(radius.0));
+        } else if radius.0 > MAP_RADIUS - PLAYER_RADIUS || target_off_map {
+            // Special-case primordus chomps
             aoe_pos.y = MAP_RADIUS;
             aoe_pos.x = player_pos.x / 3.;
         }
@@ -620,6 +795,8 @@ fn act_on_thoughts(
         .reduce(|a, b| if a.utility > b.utility { a } else { b })
         .unwrap_or(&Thought::REST);
 
+    player.reviving = matches!(best_not_shoot_thought.action, Action::Revive);
+
     act_on_thought(
         best_not_shoot_thought,
         &time,
@@ -636,6 +813,11 @@ fn act_on_thoughts(
         info!("thought caused nan: {:?}", best_not_shoot_thought);
     }
 
+    // Reviving is a channel, no shooting allowed
+    if player.reviving {
+        return;
+    }
+
     let best_shoot_thought = thoughts
         .iter()
         .filter(|a| match a.action {
@@ -719,7 +901,7 @@ fn act_on_thought(
     };
 
     match thought.action {
-        Action::Rest => {}
+        Action::Rest | Action::Revive => {}
         Action::Move(target_pos) => {
             let player_pos = player_transform.translation;
             let remaining_len = player_pos.sub(target_pos).truncate().length_squared();
@@ -851,7 +1033,8 @@ const HOME_PRIMORDUS: Vec3 = Vec3::new(
     0.,
 );
 
-fn home_for_role(game_state: &GameState, role: &AiRole) -> Vec3 {
+/// Stack spot for `role`, laid out around the boss wherever it is
+fn home_for_role(game_state: &GameState, role: &AiRole, boss_pose: &BossPose) -> Vec3 {
     let home = match game_state {
         GameState::Primordus => match role {
             AiRole::Ham1 | AiRole::Ham2 => HOME,
@@ -860,15 +1043,20 @@ fn home_for_role(game_state: &GameState, role: &AiRole) -> Vec3 {
         _ => HOME,
     };
     let offset = (*role as i32) as f32;
-    home.add(Vec3::new(
+    boss_pose.place(home.add(Vec3::new(
         (offset % 3.) * PLAYER_RADIUS,
         -(((10. - offset) / 3.) % 4.) * PLAYER_RADIUS,
         0.,
-    ))
+    )))
 }
 
-fn think_go_home(game_state: &GameState, role: &AiRole, player_pos: Vec3) -> Thought {
-    let home = home_for_role(game_state, role);
+fn think_go_home(
+    game_state: &GameState,
+    role: &AiRole,
+    boss_pose: &BossPose,
+    player_pos: Vec3,
+) -> Thought {
+    let home = home_for_role(game_state, role, boss_pose);
     if collide(player_pos, PLAYER_RADIUS * 2., home, 0.) {
         return Thought::REST;
     }
@@ -881,16 +1069,25 @@ fn think_go_home(game_state: &GameState, role: &AiRole, player_pos: Vec3) -> Tho
 
 fn think_avoid_soups(
     player_pos: Vec3,
-    soups: &Query<(&Soup, &Transform, &CollisionRadius), Without<Player>>,
+    soups: &Query<(&Soup, CollisionArea, Option<&FrostBeam>), Without<Player>>,
 ) -> Thought {
-    for (soup, transform_soup, radius) in soups {
+    for (soup, area, frost_beam) in soups {
+        if let Some(frost_beam) = frost_beam {
+            let thought =
+                think_avoid_frost_beam(player_pos, frost_beam, area.transform, area.radius);
+            if thought.utility > 0. {
+                return thought;
+            }
+            continue;
+        }
+
         if soup.damage < 0.1 {
             continue;
         }
-        let soup_pos = transform_soup.translation;
-        if !collide(player_pos, 0., soup_pos, radius.0 + PLAYER_RADIUS / 4.) {
+        if !area.collide(player_pos, PLAYER_RADIUS / 4.) {
             continue;
         }
+        let soup_pos = area.spine_point(player_pos);
 
         let diff = soup_pos.sub(player_pos);
         let utility = if soup.damage < 19. { 0.3 } else { 0.98 };
@@ -903,6 +1100,57 @@ fn think_avoid_soups(
     Thought::REST
 }
 
+fn think_avoid_frost_beam(
+    player_pos: Vec3,
+    frost_beam: &FrostBeam,
+    transform_beam: &Transform,
+    radius: &CollisionRadius,
+) -> Thought {
+    if !frost_beam.is_telegraphed() {
+        return Thought::REST;
+    }
+
+    // Treat the beam as a capsule covering where it's about to sweep
+    let beam_pos = transform_beam.translation;
+    let sweep = frost_beam
+        .predict(beam_pos, FROST_BEAM_LOOKAHEAD_S)
+        .sub(beam_pos)
+        .truncate();
+    let to_player = player_pos.sub(beam_pos).truncate();
+    let along = if sweep.length_squared() > 0.01 {
+        (to_player.dot(sweep) / sweep.length_squared()).clamp(0., 1.)
+    } else {
+        0.
+    };
+    let closest = beam_pos.add(sweep.mul(along).extend(0.));
+    if !collide(player_pos, 0., closest, radius.0 + PLAYER_RADIUS) {
+        return Thought::REST;
+    }
+
+    // Step out to the side, running ahead of a beam doesn't get you anywhere
+    let sweep_dir = sweep.normalize_or_zero();
+    let away = player_pos.sub(closest).truncate();
+    let mut sideways = away.sub(sweep_dir.mul(away.dot(sweep_dir)));
+    if sideways.length_squared() < 1. {
+        sideways = if sweep_dir.length_squared() > 0. {
+            sweep_dir.perp()
+        } else {
+            Vec2::Y
+        };
+    }
+    let target_pos = closest.add(
+        sideways
+            .normalize()
+            .mul(radius.0 + PLAYER_RADIUS * 2.)
+            .extend(0.),
+    );
+
+    Thought {
+        utility: 0.9,
+        action: Action::Move(target_pos),
+    }
+}
+
 pub fn player_ai_purification_phase_system(
     time: Res<Time>,
     game: Res<Game>,
@@ -913,12 +1161,13 @@ pub fn player_ai_purification_phase_system(
         (Entity, &mut Player, &AiPlayer, &mut Transform),
         Without<EffectForcedMarch>,
     >,
+    downed_players: Query<&GlobalTransform, With<Downed>>,
     enemies: Query<(&Enemy, &Transform), Without<Player>>,
     orb: Query<(&MobOrb, &Transform, &Velocity), Without<Player>>,
     orb_targets: Query<(&OrbTarget, &Transform), Without<Player>>,
-    soups: Query<(&Soup, &Transform, &CollisionRadius), Without<Player>>,
+    soups: Query<(&Soup, CollisionArea, Option<&FrostBeam>), Without<Player>>,
     saltspray: Query<(&MobSaltspray, &Hp)>,
-    aoes: Query<(&Aoe, &Transform, &CollisionRadius, Option<&AoeFollow>), Without<Player>>,
+    aoes: Query<AvoidableAoe, Without<Player>>,
 ) {
     let (_, orb_transform, orb_velocity) = match orb.get_single() {
         Ok(res) => res,
@@ -927,6 +1176,10 @@ pub fn player_ai_purification_phase_system(
     let orb_pos = orb_transform.translation;
 
     for (entity_player, mut player, ai_player, mut transform) in &mut players {
+        if downed_players.contains(entity_player) {
+            continue;
+        }
+
         let player_pos = transform.translation;
         let player_orb_team = get_push_team(&ai_player.role);
 
@@ -957,6 +1210,7 @@ pub fn player_ai_purification_phase_system(
             think_shoot_crab(player_pos, orb_pos, &orb_velocity, &enemies),
             think_avoid_soups(player_pos, &soups),
             think_avoid_aoes(entity_player, player_pos, &aoes),
+            think_revive(player_pos, &downed_players, &soups),
         ];
 
         if let (Some(orb_target_pos), Some(orb_dest_pos)) = (orb_target_pos, orb_dest_pos) {
@@ -1,9 +1,14 @@
+use bevy::ecs::query::QueryData;
 use bevy::prelude::*;
 use std::ops::Add;
 
-use crate::collisions::{collide, CollisionRadius};
+use crate::boons::Boon;
+use crate::collisions::{CollisionArea, CollisionRadius, CollisionShape};
 use crate::damage_flash::DamageFlashEvent;
-use crate::game::{PhaseEntity, Player, GAME_RADIUS, GAME_TO_PX, LAYER_AOE};
+use crate::fears::Fear;
+use crate::game::{Game, PhaseEntity, Player, GAME_RADIUS, GAME_TO_PX, LAYER_AOE};
+use crate::ledger::DamageSource;
+use crate::phase::EffectForcedMarch;
 
 pub const AOE_BASE_COLOR: Color = Color::srgba(0.9, 0.9, 0., 0.4);
 pub const AOE_DETONATION_COLOR: Color = Color::srgba(0.7, 0., 0., 0.7);
@@ -21,6 +26,16 @@ pub struct Aoe {
     pub damage: f32,
 }
 
+impl Aoe {
+    /// The soup left behind after detonating, if the aoe lingers
+    pub fn lingering_soup(&self, linger: Timer) -> Soup {
+        Soup {
+            damage: self.damage / 4., // arbitrary
+            duration: Some(linger),
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct AoeFollow {
     pub target: Entity,
@@ -29,10 +44,17 @@ pub struct AoeFollow {
 #[derive(Component)]
 pub struct AoeIndicator;
 
+/// Distance an aoe throws anyone it hits away from its origin
+#[derive(Component)]
+pub struct AoeKnockback(pub f32);
+
 #[derive(Clone)]
 pub struct AoeDesc {
+    pub name: &'static str,
     pub mesh: Handle<Mesh>,
+    /// Bounding radius when the aoe has a shape
     pub radius: f32,
+    pub shape: Option<CollisionShape>,
     pub material_base: Handle<ColorMaterial>,
     pub material_detonation: Handle<ColorMaterial>,
 }
@@ -79,36 +101,64 @@ pub fn aoes_system(
     }
 }
 
+#[derive(QueryData)]
+pub struct DetonatingAoe {
+    entity: Entity,
+    aoe: &'static Aoe,
+    area: CollisionArea,
+    source: &'static DamageSource,
+    knockback: Option<&'static AoeKnockback>,
+}
+
 pub fn aoes_detonation_system(
+    game: Res<Game>,
     mut commands: Commands,
     mut damage_flash_events: EventWriter<DamageFlashEvent>,
     mut players: Query<(Entity, &Transform, &mut Player)>,
-    aoes: Query<(Entity, &Aoe, &Transform, &CollisionRadius)>,
+    aoes: Query<DetonatingAoe, Without<Fear>>,
 ) {
-    for (entity_aoe, aoe, transform, radius) in &aoes {
+    for DetonatingAoeItem {
+        entity: entity_aoe,
+        aoe,
+        area,
+        source,
+        knockback,
+    } in &aoes
+    {
         if !aoe.detonation.just_finished() {
             continue;
         }
 
         for (entity_player, transform_player, mut player) in &mut players {
             let player_pos = transform_player.translation;
-            let hit = collide(transform.translation, radius.0, player_pos, 0.);
+            let hit = area.collide(player_pos, 0.);
 
-            if hit {
-                player.damage(aoe.damage, "aoe detonation");
-                player.damage_taken += aoe.damage;
+            if hit && !player.block() {
+                player.damage_taken +=
+                    player.damage(game.difficulty.mechanic_damage(aoe.damage), source.0, "aoe");
                 damage_flash_events.send(DamageFlashEvent {
                     entity: entity_player,
                 });
+
+                if let Some(knockback) = knockback {
+                    if player.invuln.finished() && !player.boons.consume(Boon::Stability) {
+                        let away = (player_pos - area.transform.translation)
+                            .truncate()
+                            .normalize_or(Vec2::NEG_Y);
+                        commands.entity(entity_player).insert(EffectForcedMarch {
+                            target: player_pos.add((away * knockback.0).extend(0.)),
+                            speed: knockback.0 / 0.2,
+                        });
+                    }
+                }
             }
         }
 
         if let Some(linger) = &aoe.linger {
             commands.entity(entity_aoe).remove::<Aoe>();
-            commands.entity(entity_aoe).insert(Soup {
-                damage: aoe.damage / 4., // arbitrary
-                duration: Some(linger.clone()),
-            });
+            commands
+                .entity(entity_aoe)
+                .insert(aoe.lingering_soup(linger.clone()));
         } else {
             commands.entity(entity_aoe).despawn_recursive();
         }
@@ -151,14 +201,34 @@ pub fn spawn_aoe(
     position: Vec3,
     aoe: Aoe,
     aoe_follow: Option<AoeFollow>,
+) -> Entity {
+    spawn_oriented_aoe(
+        commands,
+        aoe_desc,
+        position,
+        Quat::IDENTITY,
+        aoe,
+        aoe_follow,
+    )
+}
+
+/// Like spawn_aoe but with the aoe's shape rotated
+pub fn spawn_oriented_aoe(
+    commands: &mut Commands,
+    aoe_desc: &AoeDesc,
+    position: Vec3,
+    rotation: Quat,
+    aoe: Aoe,
+    aoe_follow: Option<AoeFollow>,
 ) -> Entity {
     let id = commands
         .spawn((
             Mesh2d(aoe_desc.mesh.clone()),
             MeshMaterial2d(aoe_desc.material_base.clone()),
-            Transform::from_translation(position),
+            Transform::from_translation(position).with_rotation(rotation),
             aoe,
             CollisionRadius(aoe_desc.radius),
+            DamageSource(aoe_desc.name),
             PhaseEntity,
         ))
         .with_children(|parent| {
@@ -175,6 +245,9 @@ pub fn spawn_aoe(
     if let Some(aoe_follow) = aoe_follow {
         commands.entity(id).insert(aoe_follow);
     }
+    if let Some(shape) = aoe_desc.shape {
+        commands.entity(id).insert(shape);
+    }
 
     id
 }
@@ -0,0 +1,306 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::ai::AiRole;
+use crate::downed::Downed;
+use crate::game::{Player, GAME_TO_PX};
+
+/// Fraction of incoming damage that still goes through with protection up
+pub const PROTECTION_DAMAGE_MULTIPLIER: f32 = 0.67;
+/// How much faster the shooting cooldown recovers with quickness up
+pub const QUICKNESS_COOLDOWN_RATE: f32 = 1.5;
+/// How much faster skill cooldowns recover with alacrity up
+pub const ALACRITY_COOLDOWN_RATE: f32 = 1.25;
+
+const BOON_RANGE: f32 = 600. * GAME_TO_PX;
+/// Like in game, a boon skill only reaches the closest few allies
+const BOON_TARGETS: usize = 5;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Boon {
+    Protection,
+    Stability,
+    Aegis,
+    Quickness,
+    Alacrity,
+}
+
+/// How repeated applications of the same boon combine
+pub enum BoonStacking {
+    /// Applications add onto one shared duration, up to a cap in seconds
+    Duration(f32),
+    /// Every application is its own stack with its own duration, up to a cap
+    /// in stacks
+    Intensity(usize),
+}
+
+impl Boon {
+    pub const ALL: [Boon; 5] = [
+        Boon::Quickness,
+        Boon::Alacrity,
+        Boon::Protection,
+        Boon::Aegis,
+        Boon::Stability,
+    ];
+
+    pub fn stacking(&self) -> BoonStacking {
+        match self {
+            Boon::Protection => BoonStacking::Duration(15.),
+            Boon::Aegis => BoonStacking::Duration(10.),
+            Boon::Quickness => BoonStacking::Duration(15.),
+            Boon::Alacrity => BoonStacking::Duration(15.),
+            Boon::Stability => BoonStacking::Intensity(25),
+        }
+    }
+
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            Boon::Protection => "P",
+            Boon::Stability => "S",
+            Boon::Aegis => "Ae",
+            Boon::Quickness => "Q",
+            Boon::Alacrity => "A",
+        }
+    }
+}
+
+/// Seconds left on each stack of every boon a player has
+#[derive(Default, Clone, Debug)]
+pub struct Boons {
+    stacks: HashMap<Boon, Vec<f32>>,
+}
+
+impl Boons {
+    pub fn apply(&mut self, boon: Boon, duration: f32) {
+        let stacks = self.stacks.entry(boon).or_default();
+        match boon.stacking() {
+            BoonStacking::Duration(max_duration) => {
+                let remaining = stacks.first().copied().unwrap_or(0.);
+                *stacks = vec![(remaining + duration).min(max_duration)];
+            }
+            BoonStacking::Intensity(max_stacks) => {
+                stacks.push(duration);
+                // Once full the stack closest to running out is replaced
+                if stacks.len() > max_stacks {
+                    remove_shortest(stacks);
+                }
+            }
+        }
+    }
+
+    pub fn has(&self, boon: Boon) -> bool {
+        self.stacks(boon) > 0
+    }
+
+    pub fn stacks(&self, boon: Boon) -> usize {
+        self.stacks.get(&boon).map_or(0, |stacks| stacks.len())
+    }
+
+    /// Uses up the boon to block something. Aegis is gone after one block
+    /// while stability loses the stack closest to running out
+    pub fn consume(&mut self, boon: Boon) -> bool {
+        let Some(stacks) = self.stacks.get_mut(&boon) else {
+            return false;
+        };
+        if stacks.is_empty() {
+            return false;
+        }
+        match boon.stacking() {
+            BoonStacking::Duration(_) => stacks.clear(),
+            BoonStacking::Intensity(_) => remove_shortest(stacks),
+        }
+        true
+    }
+
+    pub fn tick(&mut self, secs: f32) {
+        for stacks in self.stacks.values_mut() {
+            for remaining in stacks.iter_mut() {
+                *remaining -= secs;
+            }
+            stacks.retain(|remaining| *remaining > 0.);
+        }
+    }
+
+    /// Short summary like "Q A S3" for the squad panel
+    pub fn label(&self) -> String {
+        Boon::ALL
+            .iter()
+            .filter_map(|boon| match self.stacks(*boon) {
+                0 => None,
+                1 => Some(boon.abbreviation().to_string()),
+                n => Some(format!("{}{}", boon.abbreviation(), n)),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn remove_shortest(stacks: &mut Vec<f32>) {
+    if let Some(shortest) = stacks
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(i, _)| i)
+    {
+        stacks.swap_remove(shortest);
+    }
+}
+
+pub struct BoonCast {
+    pub boon: Boon,
+    pub duration: f32,
+    pub stacks: usize,
+    pub cooldown: Timer,
+}
+
+impl BoonCast {
+    fn new(boon: Boon, duration: f32, stacks: usize, cooldown: f32) -> BoonCast {
+        let mut cooldown = Timer::from_seconds(cooldown, TimerMode::Repeating);
+        // Open the fight with everything up
+        cooldown.set_elapsed(cooldown.duration());
+        BoonCast {
+            boon,
+            duration,
+            stacks,
+            cooldown,
+        }
+    }
+}
+
+/// Boon skills a support keeps casting on the allies around it
+#[derive(Component)]
+pub struct BoonRotation(pub Vec<BoonCast>);
+
+impl BoonRotation {
+    pub fn for_role(role: &AiRole) -> Option<BoonRotation> {
+        match role {
+            AiRole::Herald1 | AiRole::Herald2 => Some(BoonRotation(vec![
+                BoonCast::new(Boon::Quickness, 6., 1, 5.),
+                BoonCast::new(Boon::Protection, 4., 1, 8.),
+                BoonCast::new(Boon::Stability, 6., 2, 10.),
+            ])),
+            AiRole::Ham1 | AiRole::Ham2 => Some(BoonRotation(vec![
+                BoonCast::new(Boon::Alacrity, 6., 1, 5.),
+                BoonCast::new(Boon::Aegis, 4., 1, 12.),
+            ])),
+            _ => None,
+        }
+    }
+}
+
+/// The closest few players within reach of a support skill cast at `pos`,
+/// including the caster
+pub fn allies_in_range<'a>(
+    pos: Vec3,
+    players: impl Iterator<Item = (&'a Transform, Mut<'a, Player>)>,
+) -> Vec<Mut<'a, Player>> {
+    let mut targets: Vec<_> = players
+        .map(|(transform, player)| (pos.distance(transform.translation), player))
+        .filter(|(distance, _)| *distance < BOON_RANGE)
+        .collect();
+    targets.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    targets
+        .into_iter()
+        .take(BOON_TARGETS)
+        .map(|(_, player)| player)
+        .collect()
+}
+
+pub fn boon_rotation_system(
+    time: Res<Time>,
+    mut supports: Query<(&Transform, &mut BoonRotation), Without<Downed>>,
+    mut players: Query<(&Transform, &mut Player), Without<Downed>>,
+) {
+    for (transform_support, mut rotation) in &mut supports {
+        let support_pos = transform_support.translation;
+
+        for cast in rotation.0.iter_mut() {
+            cast.cooldown.tick(time.delta());
+            if !cast.cooldown.just_finished() {
+                continue;
+            }
+
+            for mut player in allies_in_range(support_pos, players.iter_mut()) {
+                for _ in 0..cast.stacks {
+                    player.boons.apply(cast.boon, cast.duration);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remaining(boons: &Boons, boon: Boon) -> Vec<f32> {
+        boons.stacks.get(&boon).cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn duration_boons_add_up_to_their_cap() {
+        let mut boons = Boons::default();
+        boons.apply(Boon::Quickness, 6.);
+        boons.apply(Boon::Quickness, 6.);
+        assert_eq!(remaining(&boons, Boon::Quickness), vec![12.]);
+
+        boons.apply(Boon::Quickness, 6.);
+        assert_eq!(remaining(&boons, Boon::Quickness), vec![15.]);
+        assert_eq!(boons.stacks(Boon::Quickness), 1);
+    }
+
+    #[test]
+    fn intensity_boons_replace_the_shortest_stack_when_full() {
+        let mut boons = Boons::default();
+        for i in 1..=25 {
+            boons.apply(Boon::Stability, i as f32);
+        }
+        assert_eq!(boons.stacks(Boon::Stability), 25);
+
+        boons.apply(Boon::Stability, 30.);
+        assert_eq!(boons.stacks(Boon::Stability), 25);
+        let stacks = remaining(&boons, Boon::Stability);
+        assert!(!stacks.contains(&1.));
+        assert!(stacks.contains(&30.));
+
+        // Shorter than everything already there, so it's the one dropped
+        boons.apply(Boon::Stability, 0.5);
+        assert!(!remaining(&boons, Boon::Stability).contains(&0.5));
+    }
+
+    #[test]
+    fn consuming_takes_one_stability_but_all_aegis() {
+        let mut boons = Boons::default();
+        assert!(!boons.consume(Boon::Stability));
+
+        boons.apply(Boon::Stability, 2.);
+        boons.apply(Boon::Stability, 5.);
+        boons.apply(Boon::Aegis, 5.);
+        boons.apply(Boon::Aegis, 5.);
+
+        // The fresh stack outlasts the older, shorter one
+        assert!(boons.consume(Boon::Stability));
+        assert_eq!(remaining(&boons, Boon::Stability), vec![5.]);
+        assert!(boons.consume(Boon::Aegis));
+        assert!(!boons.has(Boon::Aegis));
+        assert!(!boons.consume(Boon::Aegis));
+    }
+
+    #[test]
+    fn ticking_runs_out_each_stack() {
+        let mut boons = Boons::default();
+        boons.apply(Boon::Protection, 2.);
+        boons.apply(Boon::Stability, 1.);
+        boons.apply(Boon::Stability, 3.);
+        assert_eq!(boons.label(), "P S2");
+
+        boons.tick(1.5);
+        assert!(boons.has(Boon::Protection));
+        assert_eq!(boons.stacks(Boon::Stability), 1);
+
+        boons.tick(1.);
+        assert!(!boons.has(Boon::Protection));
+        assert_eq!(remaining(&boons, Boon::Stability), vec![0.5]);
+        assert_eq!(boons.label(), "S");
+    }
+}
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+
+use crate::game::{Game, HEIGHT};
+use crate::timeline::BossMove;
+
+/// Where every boss starts, just past the north edge looking south
+pub const BOSS_HOME: Vec2 = Vec2::new(0., HEIGHT / 2. + 20.);
+/// Seconds the boss takes to get to a new spot
+pub const BOSS_MOVE_DURATION_S: f32 = 3.;
+
+/// Where the boss is and which way it looks
+#[derive(Clone, Copy, Debug)]
+pub struct BossPose {
+    pub position: Vec2,
+    pub facing: Vec2,
+}
+
+impl Default for BossPose {
+    fn default() -> Self {
+        BossPose {
+            position: BOSS_HOME,
+            facing: Vec2::NEG_Y,
+        }
+    }
+}
+
+impl BossPose {
+    pub fn from_transform(transform: &Transform) -> Self {
+        BossPose {
+            position: transform.translation.truncate(),
+            facing: transform.rotation.mul_vec3(Vec3::NEG_Y).truncate(),
+        }
+    }
+
+    fn turn(&self) -> f32 {
+        Vec2::NEG_Y.angle_to(self.facing)
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_z(self.turn())
+    }
+
+    /// Moves a position laid out for the boss at [`BOSS_HOME`] so it sits
+    /// the same way relative to this pose
+    pub fn place(&self, pos: Vec3) -> Vec3 {
+        let local = pos.truncate() - BOSS_HOME;
+        let placed = self.position + Vec2::from_angle(self.turn()).rotate(local);
+        placed.extend(pos.z)
+    }
+
+    /// Partway to `other`, going around the platform rather than across it
+    fn lerp(&self, other: &BossPose, t: f32) -> Self {
+        let facing_angle = self.facing.angle_to(other.facing) * t;
+        // There's no going around the middle, so moves to or from it are straight
+        let position = if self.position == Vec2::ZERO || other.position == Vec2::ZERO {
+            self.position.lerp(other.position, t)
+        } else {
+            let angle = self.position.angle_to(other.position) * t;
+            let length = self.position.length().lerp(other.position.length(), t);
+            Vec2::from_angle(angle).rotate(self.position.normalize()) * length
+        };
+        BossPose {
+            position,
+            facing: Vec2::from_angle(facing_angle).rotate(self.facing),
+        }
+    }
+}
+
+#[derive(Component, Clone, Default)]
+pub struct BossPath {
+    pub moves: Vec<BossMove>,
+}
+
+impl BossPath {
+    pub fn pose_at(&self, secs: f32) -> BossPose {
+        let mut pose = BossPose::default();
+        for boss_move in &self.moves {
+            if secs < boss_move.start {
+                break;
+            }
+            let to = BossPose {
+                position: boss_move.position,
+                facing: boss_move
+                    .facing
+                    .unwrap_or(-boss_move.position)
+                    .normalize_or(Vec2::NEG_Y),
+            };
+            let progress = (secs - boss_move.start) / BOSS_MOVE_DURATION_S;
+            pose = pose.lerp(&to, progress.min(1.));
+        }
+        pose
+    }
+}
+
+pub fn boss_movement_system(game: Res<Game>, mut bosses: Query<(&BossPath, &mut Transform)>) {
+    let secs = game.time_elapsed.elapsed_secs();
+    for (path, mut transform) in &mut bosses {
+        let pose = path.pose_at(secs);
+        transform.translation = pose.position.extend(transform.translation.z);
+        transform.rotation = pose.rotation();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::LAYER_AOE;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    const EAST: Vec2 = Vec2::new(532., 0.);
+    const SOUTH: Vec2 = Vec2::new(0., -532.);
+
+    fn boss_move(start: f32, position: Vec2) -> BossMove {
+        BossMove {
+            start,
+            position,
+            facing: None,
+        }
+    }
+
+    fn assert_close(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.distance(expected) < 0.01,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn stays_home_until_the_first_move() {
+        let path = BossPath {
+            moves: vec![boss_move(10., EAST)],
+        };
+        let pose = path.pose_at(9.);
+        assert_close(pose.position, BOSS_HOME);
+        assert_close(pose.facing, Vec2::NEG_Y);
+    }
+
+    #[test]
+    fn completed_move_looks_at_the_middle() {
+        let path = BossPath {
+            moves: vec![boss_move(10., EAST)],
+        };
+        let pose = path.pose_at(10. + BOSS_MOVE_DURATION_S + 1.);
+        assert_close(pose.position, EAST);
+        assert_close(pose.facing, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn half_finished_move_goes_around_the_platform() {
+        let path = BossPath {
+            moves: vec![boss_move(10., EAST)],
+        };
+        let pose = path.pose_at(10. + BOSS_MOVE_DURATION_S / 2.);
+        let length = (BOSS_HOME.length() + EAST.length()) / 2.;
+        assert_close(pose.position, Vec2::splat(FRAC_1_SQRT_2) * length);
+        assert_close(pose.facing, Vec2::splat(-FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn moves_carry_on_from_the_last_one() {
+        let path = BossPath {
+            moves: vec![boss_move(10., EAST), boss_move(20., SOUTH)],
+        };
+        let pose = path.pose_at(20. + BOSS_MOVE_DURATION_S / 2.);
+        assert_close(
+            pose.position,
+            Vec2::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2) * 532.,
+        );
+
+        let pose = path.pose_at(40.);
+        assert_close(pose.position, SOUTH);
+        assert_close(pose.facing, Vec2::Y);
+    }
+
+    #[test]
+    fn moving_through_the_middle_stays_finite() {
+        let path = BossPath {
+            moves: vec![boss_move(10., Vec2::ZERO), boss_move(20., EAST)],
+        };
+        let pose = path.pose_at(10. + BOSS_MOVE_DURATION_S / 2.);
+        assert_close(pose.position, BOSS_HOME / 2.);
+        assert_close(pose.facing, Vec2::NEG_Y);
+
+        let pose = path.pose_at(20. + BOSS_MOVE_DURATION_S / 2.);
+        assert_close(pose.position, EAST / 2.);
+    }
+
+    #[test]
+    fn place_follows_a_boss_on_the_east_side() {
+        let pose = BossPose {
+            position: EAST,
+            facing: Vec2::NEG_X,
+        };
+        // Straight in front of the boss and off to its left
+        let ahead = pose.place((BOSS_HOME + Vec2::new(0., -100.)).extend(LAYER_AOE));
+        assert_close(ahead.truncate(), Vec2::new(432., 0.));
+        assert_eq!(ahead.z, LAYER_AOE);
+        let left = pose.place((BOSS_HOME + Vec2::new(50., 0.)).extend(LAYER_AOE));
+        assert_close(left.truncate(), Vec2::new(532., -50.));
+        assert_close(
+            pose.rotation().mul_vec3(Vec3::NEG_Y).truncate(),
+            Vec2::NEG_X,
+        );
+    }
+}
@@ -2,7 +2,12 @@ use bevy::prelude::*;
 
 use std::ops::Sub;
 
+use crate::boss_movement::{boss_movement_system, BossPose};
+use crate::cast_bar::cast_bar_system;
+use crate::fears::fears_system;
+use crate::frost_beams::{frost_beam_paths_system, frost_beams_despawn_system, frost_beams_system};
 use crate::mobs::*;
+use crate::timeline_bar::{timeline_bar_system, upcoming_mechanics_system, UpcomingMechanics};
 use crate::ui::boss_healthbar_system;
 use crate::waves::*;
 use crate::{ai::player_ai_boss_phase_system, aoes::*};
@@ -14,6 +19,7 @@ use crate::{
 use crate::{
     audio::{play_sfx, Sfx},
     game::*,
+    ledger::DamageSource,
 };
 
 pub const SPREAD_DAMAGE: f32 = 10.;
@@ -45,6 +51,7 @@ pub struct Puddle {
 fn spread_aoe_spawn_system(
     time: ResMut<Time>,
     players: Query<(Entity, &Transform), With<Player>>,
+    bosses: Query<&Transform, With<Boss>>,
     mut commands: Commands,
     mut spread_aoe_spawns: Query<&mut SpreadAoeSpawn>,
 ) {
@@ -59,7 +66,14 @@ fn spread_aoe_spawn_system(
         }
 
         if do_spawn {
-            for &entity_player in get_spread_target_sorted_players(&players).iter().take(6) {
+            let boss_pose = bosses
+                .get_single()
+                .map(BossPose::from_transform)
+                .unwrap_or_default();
+            for &entity_player in get_spread_target_sorted_players(&players, &boss_pose)
+                .iter()
+                .take(6)
+            {
                 spawn_aoe(
                     &mut commands,
                     &spread_aoe_spawn.aoe_desc,
@@ -100,13 +114,14 @@ pub fn boss_existence_check_system(
 
 fn get_spread_target_sorted_players(
     players: &Query<(Entity, &Transform), With<Player>>,
+    boss_pose: &BossPose,
 ) -> Vec<Entity> {
     let mut players_by_dist: Vec<(Entity, &Transform)> = vec![];
     for player in players {
         players_by_dist.push(player)
     }
 
-    let boss_center = Vec3::new(0., MAP_RADIUS, 0.);
+    let boss_center = boss_pose.place(Vec3::new(0., MAP_RADIUS, 0.));
 
     // Sort by distance to boss descending
     players_by_dist.sort_by(|b, a| {
@@ -169,6 +184,7 @@ fn puddle_spawns_system(
                     damage: 0.,
                     duration: None,
                 },
+                DamageSource("puddle"),
                 PhaseEntity,
             ));
 
@@ -211,27 +227,34 @@ fn puddles_system(
 }
 
 pub fn add_update_boss_phase_set(app: &mut App) {
-    app.add_systems(
+    app.init_resource::<UpcomingMechanics>().add_systems(
         Update,
         (
-            collisions_players_waves_system,
-            greens_system,
-            greens_detonation_system,
-            spread_aoe_spawn_system,
-            aoes_system,
-            aoes_detonation_system,
-            aoes_follow_system,
-            waves_system,
-            boss_existence_check_system,
-            boss_healthbar_system,
-            puddle_spawns_system,
-            puddles_system,
+            (
+                collisions_players_waves_system,
+                greens_system,
+                greens_detonation_system,
+                spread_aoe_spawn_system,
+                aoes_system,
+                aoes_detonation_system,
+                fears_system,
+                aoes_follow_system,
+                boss_movement_system,
+                waves_system,
+                boss_existence_check_system,
+                boss_healthbar_system,
+                cast_bar_system,
+                puddle_spawns_system,
+                puddles_system,
+                frost_beams_system,
+                frost_beams_despawn_system,
+                frost_beam_paths_system,
+            )
+                .chain(),
+            player_ai_boss_phase_system,
+            (upcoming_mechanics_system, timeline_bar_system).chain(),
         )
+            .chain()
             .in_set(PhaseSet::UpdateBossPhase),
     );
-
-    app.add_systems(
-        Update,
-        (player_ai_boss_phase_system,).in_set(PhaseSet::UpdateBossPhase),
-    );
 }
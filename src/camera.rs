@@ -0,0 +1,113 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::{camera::CameraProjection, view::RenderLayers},
+};
+
+use crate::game::{Game, HumanPlayer, LAYER_MAX};
+use crate::input::{InputAction, InputBindings};
+
+/// Render layer for HUD sprites and text, drawn by a camera that never moves
+pub const HUD_RENDER_LAYER: usize = 1;
+
+pub const CAMERA_ZOOM_MIN: f32 = 1.;
+pub const CAMERA_ZOOM_MAX: f32 = 3.;
+const CAMERA_ZOOM_STEP: f32 = 0.25;
+/// Scroll wheel pixels that count as one zoom step on touchpads
+const CAMERA_ZOOM_PIXELS_PER_STEP: f32 = 40.;
+
+/// Looks at the arena, following the human players when that is turned on
+#[derive(Component)]
+pub struct WorldCamera;
+
+#[derive(Component)]
+pub struct HudCamera;
+
+pub fn hud_layer() -> RenderLayers {
+    RenderLayers::layer(HUD_RENDER_LAYER)
+}
+
+pub fn spawn_cameras(commands: &mut Commands) {
+    let projection = OrthographicProjection {
+        far: LAYER_MAX,
+        ..OrthographicProjection::default_2d()
+    };
+    let transform = Transform::from_xyz(0., 0., LAYER_MAX - 0.1);
+    let frustum = projection.compute_frustum(&GlobalTransform::from(transform));
+
+    commands.spawn((
+        Camera2d,
+        projection.clone(),
+        transform,
+        frustum,
+        WorldCamera,
+    ));
+
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: 1,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        projection,
+        transform,
+        frustum,
+        hud_layer(),
+        HudCamera,
+        IsDefaultUiCamera,
+    ));
+}
+
+pub fn camera_zoom_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    bindings: Res<InputBindings>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut game: ResMut<Game>,
+) {
+    let mut steps = 0.;
+    for event in mouse_wheel_events.read() {
+        steps += match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / CAMERA_ZOOM_PIXELS_PER_STEP,
+        };
+    }
+    if bindings.just_pressed(InputAction::ZoomIn, &keyboard_input, &mouse_button_input) {
+        steps += 1.;
+    }
+    if bindings.just_pressed(InputAction::ZoomOut, &keyboard_input, &mouse_button_input) {
+        steps -= 1.;
+    }
+
+    game.camera_zoom =
+        (game.camera_zoom + steps * CAMERA_ZOOM_STEP).clamp(CAMERA_ZOOM_MIN, CAMERA_ZOOM_MAX);
+}
+
+pub fn camera_follow_system(
+    game: Res<Game>,
+    players: Query<(&Transform, &HumanPlayer)>,
+    mut cameras: Query<&mut Transform, (With<WorldCamera>, Without<HumanPlayer>)>,
+    mut projections: Query<&mut OrthographicProjection, With<WorldCamera>>,
+) {
+    // Local co-op keeps both humans in view by following their midpoint
+    let positions: Vec<Vec2> = players
+        .iter()
+        .filter(|(_, human_player)| human_player.is_local())
+        .map(|(transform_player, _)| transform_player.translation.truncate())
+        .collect();
+    let (center, scale) = if game.camera_follow_enabled && !positions.is_empty() {
+        let sum: Vec2 = positions.iter().sum();
+        (sum / positions.len() as f32, 1. / game.camera_zoom)
+    } else {
+        (Vec2::ZERO, 1.)
+    };
+
+    for mut transform in &mut cameras {
+        transform.translation.x = center.x;
+        transform.translation.y = center.y;
+    }
+    for mut projection in &mut projections {
+        projection.scale = scale;
+    }
+}
@@ -0,0 +1,144 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::aoes::Aoe;
+use crate::camera::hud_layer;
+use crate::frost_beams::{FrostBeam, FROST_BEAM_TELEGRAPH_S};
+use crate::game::{PhaseEntity, HEIGHT, LAYER_TEXT, LAYER_UI, WIDTH};
+use crate::ledger::DamageSource;
+use crate::waves::Wave;
+
+const CAST_BAR_WIDTH: f32 = 256.;
+const CAST_BAR_HEIGHT: f32 = 20.;
+const CAST_BAR_X: f32 = -WIDTH / 2. + 20.;
+/// Just under the boss health bar
+const CAST_BAR_Y: f32 = -HEIGHT / 2. + 128. + 24. - 16. - 6. - CAST_BAR_HEIGHT / 2.;
+/// Waves show up without a telegraph, so their cast starts this long before
+const WAVE_CAST_S: f32 = 2.;
+
+#[derive(Component)]
+pub struct CastBar;
+
+#[derive(Component)]
+pub struct CastBarFill;
+
+#[derive(Component)]
+pub struct CastBarText;
+
+/// What the boss is casting when an aoe named `source` is winding up
+fn ability_name(source: &str) -> Option<&'static str> {
+    match source {
+        "chomp" | "mini chomp" => Some("Jaws of Destruction"),
+        "claw swipe" => Some("Claw Swipe"),
+        "tail slam" => Some("Tail Slam"),
+        "bite" => Some("Bite"),
+        "fear" => Some("Scream"),
+        "line" => Some("Lightning Lines"),
+        "spew" => Some("Poison Spew"),
+        _ => None,
+    }
+}
+
+pub fn setup_cast_bar(commands: &mut Commands, asset_server: &Res<AssetServer>) {
+    commands
+        .spawn((
+            Sprite {
+                color: Color::srgba(0., 0., 0., 0.6),
+                custom_size: Some(Vec2::new(CAST_BAR_WIDTH, CAST_BAR_HEIGHT)),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            Transform::from_xyz(CAST_BAR_X, CAST_BAR_Y, LAYER_UI),
+            Visibility::Hidden,
+            CastBar,
+            hud_layer(),
+            PhaseEntity,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Sprite {
+                    color: Color::srgb(0.9, 0.6, 0.1),
+                    custom_size: Some(Vec2::new(CAST_BAR_WIDTH, CAST_BAR_HEIGHT)),
+                    anchor: Anchor::CenterLeft,
+                    ..default()
+                },
+                Transform::from_xyz(0., 0., 0.1).with_scale(Vec3::new(0., 1., 1.)),
+                CastBarFill,
+                hud_layer(),
+            ));
+            parent.spawn((
+                Text2d::new(""),
+                TextFont {
+                    font: asset_server.load("trebuchet_ms.ttf"),
+                    font_size: 14.,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Anchor::Center,
+                Transform::from_xyz(CAST_BAR_WIDTH / 2., 0., LAYER_TEXT - LAYER_UI),
+                CastBarText,
+                hud_layer(),
+            ));
+        });
+}
+
+/// Shows whichever ability is closest to going off, filling over its wind-up
+pub fn cast_bar_system(
+    aoes: Query<(&Aoe, &DamageSource)>,
+    waves: Query<&Wave>,
+    frost_beams: Query<&FrostBeam>,
+    mut cast_bars: Query<&mut Visibility, With<CastBar>>,
+    mut fills: Query<&mut Transform, With<CastBarFill>>,
+    mut texts: Query<&mut Text2d, With<CastBarText>>,
+) {
+    let mut casts: Vec<(&'static str, f32)> = vec![];
+
+    for (aoe, source) in &aoes {
+        let Some(name) = ability_name(source.0) else {
+            continue;
+        };
+        let visible = aoe
+            .visibility_start
+            .as_ref()
+            .is_none_or(|timer| timer.finished());
+        if visible && !aoe.detonation.finished() {
+            casts.push((name, aoe.detonation.fraction()));
+        }
+    }
+
+    for wave in &waves {
+        let remaining = wave.visibility_start.remaining_secs();
+        if !wave.visibility_start.finished() && remaining < WAVE_CAST_S {
+            casts.push(("Tsunami", 1. - remaining / WAVE_CAST_S));
+        }
+    }
+
+    for beam in &frost_beams {
+        if beam.is_telegraphed() && !beam.start.finished() {
+            let remaining = beam.start.remaining_secs();
+            casts.push(("Frost Beam", 1. - remaining / FROST_BEAM_TELEGRAPH_S));
+        }
+    }
+
+    let cast = casts.into_iter().max_by(|a, b| a.1.total_cmp(&b.1));
+
+    for mut visibility in &mut cast_bars {
+        *visibility = if cast.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    let Some((name, progress)) = cast else {
+        return;
+    };
+    for mut transform in &mut fills {
+        transform.scale.x = progress;
+    }
+    for mut text in &mut texts {
+        if text.0 != name {
+            text.0 = name.to_string();
+        }
+    }
+}
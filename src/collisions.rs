@@ -1,11 +1,15 @@
+use bevy::ecs::query::QueryData;
 use bevy::prelude::*;
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::ops::{Add, Mul, Sub};
 
 use crate::aoes::*;
 use crate::audio::{play_sfx, Sfx, SfxSource};
+use crate::boons::Boon;
 use crate::game::*;
+use crate::ledger::DamageSource;
 use crate::mobs::*;
 use crate::orbs::*;
 use crate::phase::{EffectForcedMarch, Velocity};
@@ -16,6 +20,149 @@ use crate::damage_flash::*;
 #[derive(Component)]
 pub struct CollisionRadius(pub f32);
 
+/// Non-circular hit area, oriented by the entity's rotation. The entity's
+/// CollisionRadius is kept as a bounding circle around it
+#[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum CollisionShape {
+    /// Wedge opening towards local +y from the entity's position
+    Cone {
+        radius: f32,
+        half_angle: f32,
+    },
+    Rect {
+        half_size: Vec2,
+    },
+    /// Segment along local y with rounded ends
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
+    Donut {
+        inner_radius: f32,
+        outer_radius: f32,
+    },
+}
+
+impl CollisionShape {
+    pub fn bounding_radius(&self) -> f32 {
+        match *self {
+            CollisionShape::Cone { radius, .. } => radius,
+            CollisionShape::Rect { half_size } => half_size.length(),
+            CollisionShape::Capsule {
+                half_length,
+                radius,
+            } => half_length + radius,
+            CollisionShape::Donut { outer_radius, .. } => outer_radius,
+        }
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        match *self {
+            CollisionShape::Cone { radius, half_angle } => {
+                CircularSector::new(radius, half_angle).into()
+            }
+            CollisionShape::Rect { half_size } => Rectangle::from_size(half_size * 2.).into(),
+            CollisionShape::Capsule {
+                half_length,
+                radius,
+            } => Capsule2d::new(radius, half_length * 2.).into(),
+            CollisionShape::Donut {
+                inner_radius,
+                outer_radius,
+            } => Annulus::new(inner_radius, outer_radius).into(),
+        }
+    }
+
+    fn to_local(transform: &Transform, pos: Vec3) -> Vec2 {
+        transform
+            .rotation
+            .inverse()
+            .mul_vec3(pos.sub(transform.translation))
+            .truncate()
+    }
+
+    fn from_local(transform: &Transform, local: Vec2) -> Vec3 {
+        let mut pos = transform
+            .translation
+            .add(transform.rotation.mul_vec3(local.extend(0.)));
+        pos.z = transform.translation.z;
+        pos
+    }
+
+    /// Whether a circle of `radius` at `pos` overlaps the shape
+    pub fn collide(&self, transform: &Transform, pos: Vec3, radius: f32) -> bool {
+        let local = CollisionShape::to_local(transform, pos);
+        match *self {
+            CollisionShape::Cone {
+                radius: cone_radius,
+                half_angle,
+            } => {
+                let dist = local.length();
+                if dist > cone_radius + radius {
+                    return false;
+                }
+                // The apex has no angle to check against
+                if dist == 0. || local.angle_to(Vec2::Y).abs() <= half_angle {
+                    return true;
+                }
+                // Otherwise only the straight edges can be close enough
+                let (sin, cos) = half_angle.sin_cos();
+                [Vec2::new(-sin, cos), Vec2::new(sin, cos)]
+                    .iter()
+                    .any(|edge| {
+                        let along = local.dot(*edge).clamp(0., cone_radius);
+                        local.distance_squared(edge.mul(along)) < radius * radius
+                    })
+            }
+            CollisionShape::Rect { half_size } => {
+                let closest = local.clamp(-half_size, half_size);
+                local.distance_squared(closest) <= radius * radius
+            }
+            CollisionShape::Capsule {
+                half_length,
+                radius: capsule_radius,
+            } => {
+                let closest = Vec2::new(0., local.y.clamp(-half_length, half_length));
+                let reach = capsule_radius + radius;
+                local.distance_squared(closest) < reach * reach
+            }
+            CollisionShape::Donut {
+                inner_radius,
+                outer_radius,
+            } => {
+                let dist = local.length();
+                dist + radius >= inner_radius && dist - radius < outer_radius
+            }
+        }
+    }
+
+    /// Closest point to `pos` along the middle of the shape. Walking directly
+    /// away from it is the quickest way out
+    pub fn spine_point(&self, transform: &Transform, pos: Vec3) -> Vec3 {
+        let local = CollisionShape::to_local(transform, pos);
+        let spine = match *self {
+            CollisionShape::Cone { radius, .. } => Vec2::new(0., local.y.clamp(0., radius)),
+            CollisionShape::Rect { half_size } => {
+                if half_size.y > half_size.x {
+                    Vec2::new(0., local.y.clamp(-half_size.y, half_size.y))
+                } else {
+                    Vec2::new(local.x.clamp(-half_size.x, half_size.x), 0.)
+                }
+            }
+            CollisionShape::Capsule { half_length, .. } => {
+                Vec2::new(0., local.y.clamp(-half_length, half_length))
+            }
+            CollisionShape::Donut {
+                inner_radius,
+                outer_radius,
+            } => local
+                .normalize_or(Vec2::Y)
+                .mul((inner_radius + outer_radius) / 2.),
+        };
+        CollisionShape::from_local(transform, spine)
+    }
+}
+
 #[derive(Component)]
 pub struct HasHit(pub HashSet<Entity>);
 
@@ -25,6 +172,32 @@ pub fn collide(pos_a: Vec3, radius_a: f32, pos_b: Vec3, radius_b: f32) -> bool {
     return diff.length_squared() < (radius_a + radius_b) * (radius_a + radius_b);
 }
 
+/// Where an entity hits, using its CollisionShape if it has one and its
+/// CollisionRadius otherwise
+#[derive(QueryData)]
+pub struct CollisionArea {
+    pub transform: &'static Transform,
+    pub radius: &'static CollisionRadius,
+    pub shape: Option<&'static CollisionShape>,
+}
+
+impl CollisionAreaItem<'_> {
+    pub fn collide(&self, pos: Vec3, radius: f32) -> bool {
+        match self.shape {
+            Some(shape) => shape.collide(self.transform, pos, radius),
+            None => collide(self.transform.translation, self.radius.0, pos, radius),
+        }
+    }
+
+    /// See CollisionShape::spine_point, circles are just their center
+    pub fn spine_point(&self, pos: Vec3) -> Vec3 {
+        match self.shape {
+            Some(shape) => shape.spine_point(self.transform, pos),
+            None => self.transform.translation,
+        }
+    }
+}
+
 fn bullet_damage(bullet: &Bullet) -> f32 {
     let dist_traveled = bullet.age * BULLET_SPEED;
     // Reward being close to the target with more damage
@@ -124,7 +297,7 @@ pub fn collisions_crabs_orbs_system(
             let crab_pos = transform_crab.translation;
             if collide(orb_pos, ORB_RADIUS, crab_pos, CRAB_SIZE / 2.) {
                 for mut player in &mut players {
-                    player.damage(999., "crab hit orb");
+                    player.damage(999., "crab hit orb", "crab");
                 }
                 play_sfx(
                     &mut commands,
@@ -204,7 +377,7 @@ pub fn collisions_orb_targets_system(
 pub fn collisions_players_edge_system(mut players: Query<(&mut Player, &Transform)>) {
     for (mut player, transform_player) in &mut players {
         if !collide(transform_player.translation, 0., Vec3::ZERO, MAP_RADIUS) {
-            player.damage(999., "player fell off the edge");
+            player.damage(999., "fell off the edge", "edge");
             info!("player fell off the edge: {}", transform_player.translation);
         }
     }
@@ -212,6 +385,7 @@ pub fn collisions_players_edge_system(mut players: Query<(&mut Player, &Transfor
 
 pub fn collisions_players_echo_system(
     time: Res<Time>,
+    game: Res<Game>,
     mut players: Query<(&mut Player, &Transform)>,
     mut echos: Query<(&mut MobEcho, &Transform, &CollisionRadius), Without<Player>>,
 ) {
@@ -234,27 +408,32 @@ pub fn collisions_players_echo_system(
 
             echo.gottem = true;
 
-            player.damage(ECHO_DAMAGE * time.delta_secs(), "echo hug");
-            player.damage_taken += ECHO_DAMAGE * time.delta_secs();
+            let damage = game
+                .difficulty
+                .mechanic_damage(ECHO_DAMAGE * time.delta_secs());
+            player.damage_taken += player.damage(damage, "echo hug", "echo");
         }
     }
 }
 
 pub fn collisions_players_soups_system(
     time: Res<Time>,
+    game: Res<Game>,
     mut damage_flash_events: EventWriter<DamageFlashEvent>,
     mut players: Query<(Entity, &Transform, &mut Player)>,
-    soups: Query<(&Soup, &Transform, &CollisionRadius)>,
+    soups: Query<(&Soup, CollisionArea, Option<&DamageSource>)>,
 ) {
     for (entity_player, transform_player, mut player) in &mut players {
         let player_pos = transform_player.translation;
-        for (soup, transform_soup, radius) in &soups {
-            if !collide(player_pos, 0., transform_soup.translation, radius.0) {
+        for (soup, area, source) in &soups {
+            if !area.collide(player_pos, 0.) {
                 continue;
             }
-            let damage = soup.damage * time.delta_secs();
-            player.damage(damage, "soup");
-            player.damage_taken += damage;
+            let damage = game
+                .difficulty
+                .mechanic_damage(soup.damage * time.delta_secs());
+            player.damage_taken +=
+                player.damage(damage, source.map_or("soup", |source| source.0), "soup");
             if soup.damage > 0.1 {
                 damage_flash_events.send(DamageFlashEvent {
                     entity: entity_player,
@@ -265,6 +444,7 @@ pub fn collisions_players_soups_system(
 }
 
 pub fn collisions_players_waves_system(
+    game: Res<Game>,
     mut damage_flash_events: EventWriter<DamageFlashEvent>,
     mut players: Query<(Entity, &Transform, &mut Player), Without<EffectForcedMarch>>,
     waves: Query<(&Wave, &Visibility, &Transform)>,
@@ -281,20 +461,15 @@ pub fn collisions_players_waves_system(
                 continue;
             }
 
-            let r_outer = transform.scale.x * WAVE_MAX_RADIUS;
-            let r_inner = r_outer - 20.;
-
-            // Safe because we're in the "eye" of the wave
-            if collide(player_pos, 0., transform.translation, r_inner) {
-                continue;
-            }
-            if collide(player_pos, 0., transform.translation, r_outer) {
+            if wave_shape(transform).collide(transform, player_pos, 0.) {
                 if player.invuln.finished() && player.jump.finished() {
-                    player.damage(WAVE_DAMAGE, "wave");
-                    player.damage_taken += WAVE_DAMAGE;
-                    damage_flash_events.send(DamageFlashEvent {
-                        entity: entity_player,
-                    });
+                    if !player.block() {
+                        let damage = game.difficulty.mechanic_damage(WAVE_DAMAGE);
+                        player.damage_taken += player.damage(damage, "wave", "wave");
+                        damage_flash_events.send(DamageFlashEvent {
+                            entity: entity_player,
+                        });
+                    }
                     // Brief invuln from being knocked (not actually knocked because Reasons)
                     player.invuln = Timer::from_seconds(1., TimerMode::Once);
                 }
@@ -317,7 +492,7 @@ pub fn collisions_orbs_edge_system(
             MAP_RADIUS - ORB_RADIUS,
         ) {
             for mut player in &mut players {
-                player.damage(999., "orb hit the edge");
+                player.damage(999., "orb hit the edge", "orb");
             }
             info!("orb hit the edge: {}", transform_orb.translation);
 
@@ -332,6 +507,7 @@ pub fn collisions_orbs_edge_system(
 }
 
 pub fn collisions_players_enemy_bullets_system(
+    game: Res<Game>,
     mut damage_flash_events: EventWriter<DamageFlashEvent>,
     mut commands: Commands,
     mut players: Query<(Entity, &Transform, &mut Player)>,
@@ -342,8 +518,12 @@ pub fn collisions_players_enemy_bullets_system(
         &Velocity,
         &CollisionRadius,
     )>,
+    sources: Query<&DamageSource>,
 ) {
     for (entity_bullet, bullet, transform_bullet, velocity, radius) in &bullets {
+        let mechanic = sources
+            .get(entity_bullet)
+            .map_or("bullet", |source| source.0);
         for (entity_player, transform_player, mut player) in &mut players {
             let player_pos = transform_player.translation;
 
@@ -356,14 +536,14 @@ pub fn collisions_players_enemy_bullets_system(
                 continue;
             }
 
-            if player.invuln.finished() {
-                player.damage(bullet.damage, "bullet");
-                player.damage_taken += bullet.damage;
+            if player.invuln.finished() && !player.block() {
+                let damage = game.difficulty.mechanic_damage(bullet.damage);
+                player.damage_taken += player.damage(damage, mechanic, "bullet");
                 damage_flash_events.send(DamageFlashEvent {
                     entity: entity_player,
                 });
 
-                if bullet.knockback.abs() > 0.1 {
+                if bullet.knockback.abs() > 0.1 && !player.boons.consume(Boon::Stability) {
                     let target =
                         player_pos.add(velocity.0.clamp_length(bullet.knockback, bullet.knockback));
                     let speed = bullet.knockback / 0.2;
@@ -379,3 +559,110 @@ pub fn collisions_players_enemy_bullets_system(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    const CONE: CollisionShape = CollisionShape::Cone {
+        radius: 100.,
+        half_angle: FRAC_PI_4,
+    };
+    const RECT: CollisionShape = CollisionShape::Rect {
+        half_size: Vec2::new(20., 50.),
+    };
+    const CAPSULE: CollisionShape = CollisionShape::Capsule {
+        half_length: 50.,
+        radius: 10.,
+    };
+    const DONUT: CollisionShape = CollisionShape::Donut {
+        inner_radius: 80.,
+        outer_radius: 100.,
+    };
+
+    /// Local +y turned to face world -x, with the shape moved off the origin
+    fn turned() -> Transform {
+        Transform::from_xyz(200., 100., LAYER_AOE).with_rotation(Quat::from_rotation_z(FRAC_PI_2))
+    }
+
+    fn hits(shape: CollisionShape, transform: &Transform, x: f32, y: f32, radius: f32) -> bool {
+        shape.collide(transform, Vec3::new(x, y, LAYER_PLAYER), radius)
+    }
+
+    #[test]
+    fn cone() {
+        let t = Transform::default();
+        assert!(hits(CONE, &t, 0., 50., 0.));
+        assert!(hits(CONE, &t, 0., 99.9, 0.));
+        assert!(!hits(CONE, &t, 0., 100.1, 0.));
+        assert!(hits(CONE, &t, 0., 105., 10.));
+        assert!(!hits(CONE, &t, 0., -10., 0.));
+        // Either side of a straight edge
+        assert!(hits(CONE, &t, 49., 50., 0.));
+        assert!(!hits(CONE, &t, 60., 50., 0.));
+        assert!(hits(CONE, &t, 60., 50., 10.));
+        assert!(!hits(CONE, &t, -60., 50., 5.));
+        // Anyone standing right on the apex
+        assert!(hits(CONE, &t, 0., 0., 0.));
+        assert!(hits(CONE, &turned(), 200., 100., 0.));
+
+        let t = turned();
+        assert!(hits(CONE, &t, 150., 100., 0.));
+        assert!(!hits(CONE, &t, 250., 100., 0.));
+        assert!(!hits(CONE, &t, 200., 150., 0.));
+    }
+
+    #[test]
+    fn rect() {
+        let t = Transform::default();
+        assert!(hits(RECT, &t, 0., 0., 0.));
+        assert!(hits(RECT, &t, 20., 50., 0.));
+        assert!(!hits(RECT, &t, 21., 50., 0.));
+        assert!(!hits(RECT, &t, 0., 51., 0.));
+        // Past the corner, but only just reaching it
+        assert!(hits(RECT, &t, 23., 54., 5.));
+        assert!(!hits(RECT, &t, 23., 54., 4.9));
+
+        let t = turned();
+        assert!(hits(RECT, &t, 150.1, 100., 0.));
+        assert!(!hits(RECT, &t, 200., 125., 0.));
+        assert!(hits(RECT, &t, 200., 125., 5.1));
+    }
+
+    #[test]
+    fn capsule() {
+        let t = Transform::default();
+        assert!(hits(CAPSULE, &t, 0., 0., 0.));
+        assert!(hits(CAPSULE, &t, 9.9, 0., 0.));
+        assert!(!hits(CAPSULE, &t, 10.1, 0., 0.));
+        // Around the rounded caps
+        assert!(hits(CAPSULE, &t, 0., 59.9, 0.));
+        assert!(!hits(CAPSULE, &t, 0., -60.1, 0.));
+        assert!(hits(CAPSULE, &t, 7., 57., 0.));
+        assert!(!hits(CAPSULE, &t, 8., 57., 0.));
+        assert!(hits(CAPSULE, &t, 8., 57., 1.));
+
+        let t = turned();
+        assert!(hits(CAPSULE, &t, 145., 100., 0.));
+        assert!(!hits(CAPSULE, &t, 200., 115., 0.));
+    }
+
+    #[test]
+    fn donut() {
+        let t = Transform::default();
+        assert!(hits(DONUT, &t, 0., 90., 0.));
+        assert!(hits(DONUT, &t, -80., 0., 0.));
+        assert!(!hits(DONUT, &t, 0., 100., 0.));
+        // Safe in the eye unless reaching the inner edge
+        assert!(!hits(DONUT, &t, 0., 0., 0.));
+        assert!(!hits(DONUT, &t, 0., 70., 9.));
+        assert!(hits(DONUT, &t, 0., 70., 11.));
+        assert!(!hits(DONUT, &t, 0., 105., 5.));
+        assert!(hits(DONUT, &t, 0., 105., 6.));
+
+        let t = turned();
+        assert!(hits(DONUT, &t, 290., 100., 0.));
+        assert!(!hits(DONUT, &t, 200., 100., 0.));
+    }
+}
@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use crate::collisions::collide;
+use crate::game::{Player, PLAYER_RADIUS};
+
+/// Downed players have a fresh pool of hp that drains over this long
+pub const DOWNED_BLEED_OUT_S: f32 = 20.;
+pub const DOWNED_HP: f32 = 100.;
+pub const DOWNED_SPEED_SCALE: f32 = 0.25;
+pub const REVIVE_RANGE: f32 = PLAYER_RADIUS * 3.;
+/// How long one player takes to revive someone, more players stack
+pub const REVIVE_DURATION_S: f32 = 3.;
+pub const REVIVE_HP: f32 = 50.;
+
+#[derive(Component)]
+pub struct Downed {
+    pub revive: Timer,
+}
+
+#[derive(Component)]
+pub struct DownedMarker;
+
+pub fn down_player(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    entity_player: Entity,
+    player: &mut Player,
+) {
+    info!("{} is downed", player.name);
+    player.set_hp(DOWNED_HP);
+    player.reviving = false;
+
+    commands
+        .entity(entity_player)
+        .insert(Downed {
+            revive: Timer::from_seconds(REVIVE_DURATION_S, TimerMode::Once),
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                Sprite {
+                    color: Color::srgb(0.9, 0.1, 0.1),
+                    custom_size: Some(Vec2::new(REVIVE_RANGE * 2., REVIVE_RANGE * 2.)),
+                    image: asset_server.load("ring.png"),
+                    ..default()
+                },
+                Transform::from_xyz(0., 0., -0.1),
+                DownedMarker,
+            ));
+        });
+}
+
+pub fn downed_bleed_out_system(time: Res<Time>, mut players: Query<&mut Player, With<Downed>>) {
+    for mut player in &mut players {
+        let hp = player.get_hp() - DOWNED_HP / DOWNED_BLEED_OUT_S * time.delta_secs();
+        player.set_hp(hp);
+    }
+}
+
+pub fn revive_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    revivers: Query<(&Player, &Transform), Without<Downed>>,
+    mut downed_players: Query<(Entity, &mut Player, &mut Downed, &Transform, &Children)>,
+    mut markers: Query<&mut Sprite, With<DownedMarker>>,
+) {
+    for (entity_player, mut player, mut downed, transform, children) in &mut downed_players {
+        let player_pos = transform.translation;
+        let n_revivers = revivers
+            .iter()
+            .filter(|(reviver, transform_reviver)| {
+                reviver.reviving
+                    && collide(player_pos, REVIVE_RANGE, transform_reviver.translation, 0.)
+            })
+            .count();
+
+        downed.revive.tick(time.delta().mul_f32(n_revivers as f32));
+
+        for &child in children.iter() {
+            if let Ok(mut sprite) = markers.get_mut(child) {
+                let progress = downed.revive.fraction();
+                sprite.color = Color::srgb(0.9 - 0.8 * progress, 0.1 + 0.8 * progress, 0.1);
+            }
+        }
+
+        if !downed.revive.finished() {
+            continue;
+        }
+
+        info!("{} was revived", player.name);
+        player.set_hp(REVIVE_HP);
+        commands.entity(entity_player).remove::<Downed>();
+        for &child in children.iter() {
+            if markers.contains(child) {
+                commands.entity(child).despawn_recursive();
+            }
+        }
+    }
+}
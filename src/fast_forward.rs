@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::aoes::Aoe;
+use crate::boss_phase::{PuddleSpawn, SpreadAoeSpawn};
+use crate::frost_beams::FrostBeam;
+use crate::game::Game;
+use crate::greens::StackGreen;
+use crate::hints::ScheduledHint;
+use crate::mobs::MobNoodle;
+use crate::phase::RotatingSoup;
+use crate::waves::Wave;
+
+/// Seconds to skip the freshly set up phase ahead by, removed once every
+/// scheduled mechanic has been moved along. The void zone is left at its
+/// starting size so that late mechanics stay playable
+#[derive(Resource)]
+pub struct FastForward(pub f32);
+
+/// Ticks `timer` by `secs`, returning how many seconds were left over after
+/// it finished
+fn tick_overflow(timer: &mut Timer, secs: f32) -> f32 {
+    let overflow = secs - timer.remaining_secs();
+    timer.tick(Duration::from_secs_f32(secs.max(0.)));
+    overflow
+}
+
+pub fn fast_forward_aoes_system(
+    mut commands: Commands,
+    fast_forward: Res<FastForward>,
+    mut aoes: Query<(Entity, &mut Aoe)>,
+    mut spread_aoe_spawns: Query<&mut SpreadAoeSpawn>,
+    mut puddle_spawns: Query<(Entity, &mut PuddleSpawn)>,
+) {
+    let secs = fast_forward.0;
+
+    for (entity, mut aoe) in &mut aoes {
+        let shown_for = match &mut aoe.visibility_start {
+            Some(timer) => tick_overflow(timer, secs),
+            None => secs,
+        };
+        if shown_for <= 0. {
+            continue;
+        }
+
+        let detonated_for = tick_overflow(&mut aoe.detonation, shown_for);
+        if detonated_for < 0. {
+            continue;
+        }
+
+        // Already went off, so only a lingering soup can be left over
+        let Some(mut linger) = aoe.linger.clone() else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        if tick_overflow(&mut linger, detonated_for) >= 0. {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        commands.entity(entity).remove::<Aoe>();
+        commands.entity(entity).insert(aoe.lingering_soup(linger));
+    }
+
+    for mut spread_aoe_spawn in &mut spread_aoe_spawns {
+        spread_aoe_spawn
+            .timers
+            .retain_mut(|timer| tick_overflow(timer, secs) < 0.);
+    }
+
+    // Where earlier puddles would have been dropped depends on the players,
+    // so those are skipped entirely
+    for (entity, mut puddle_spawn) in &mut puddle_spawns {
+        if tick_overflow(&mut puddle_spawn.visibility_start, secs) >= 0. {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn fast_forward_mechanics_system(
+    mut commands: Commands,
+    fast_forward: Res<FastForward>,
+    mut waves: Query<(Entity, &mut Wave)>,
+    mut greens: Query<(Entity, &mut StackGreen)>,
+    mut noodles: Query<&mut MobNoodle>,
+    mut frost_beams: Query<(&mut FrostBeam, &mut Transform)>,
+) {
+    let secs = fast_forward.0;
+
+    for (entity, mut wave) in &mut waves {
+        let growing_for = tick_overflow(&mut wave.visibility_start, secs);
+        if growing_for > 0. && tick_overflow(&mut wave.growth, growing_for) >= 0. {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for (entity, mut green) in &mut greens {
+        let shown_for = tick_overflow(&mut green.visibility_start, secs);
+        if shown_for > 0. && tick_overflow(&mut green.detonation, shown_for) >= 0. {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for mut noodle in &mut noodles {
+        let alive_for = tick_overflow(&mut noodle.visibility_start, secs);
+        if alive_for > 0. {
+            let slam_duration = noodle.slam_cooldown.duration().as_secs_f32();
+            let slam_elapsed = alive_for % slam_duration;
+            noodle
+                .slam_cooldown
+                .tick(Duration::from_secs_f32(slam_elapsed));
+        }
+    }
+
+    for (mut beam, mut transform) in &mut frost_beams {
+        transform.translation = beam.predict(transform.translation, secs);
+        beam.start.tick(Duration::from_secs_f32(secs));
+        beam.pass_waypoints(transform.translation);
+    }
+}
+
+pub fn fast_forward_phase_system(
+    mut commands: Commands,
+    fast_forward: Res<FastForward>,
+    mut game: ResMut<Game>,
+    mut hints: Query<(Entity, &mut ScheduledHint)>,
+    mut rotating_soups: Query<(&mut Transform, &mut RotatingSoup)>,
+) {
+    let secs = fast_forward.0;
+
+    game.time_elapsed.set_elapsed(Duration::from_secs_f32(secs));
+
+    for (entity, mut hint) in &mut hints {
+        if tick_overflow(&mut hint.start, secs) >= 0. {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for (mut transform, mut soup) in &mut rotating_soups {
+        soup.theta += soup.dtheta * secs;
+        transform.translation.x = soup.theta.cos() * soup.radius;
+        transform.translation.y = soup.theta.sin() * soup.radius;
+    }
+
+    commands.remove_resource::<FastForward>();
+}
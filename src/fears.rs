@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use crate::aoes::Aoe;
+use crate::boons::Boon;
+use crate::downed::Downed;
+use crate::game::{Player, GAME_TO_PX};
+use crate::mobs::Boss;
+use crate::phase::EffectForcedMarch;
+
+/// Feared players walk away at roughly normal movement speed
+pub const FEAR_SPEED: f32 = 250. * GAME_TO_PX;
+pub const FEAR_DURATION_S: f32 = 2.;
+pub const FEAR_DISTANCE: f32 = FEAR_SPEED * FEAR_DURATION_S;
+
+/// Marks the aoe telegraphing a scream. Instead of dealing damage it fears
+/// everyone it catches when it goes off
+#[derive(Component)]
+pub struct Fear;
+
+/// Where someone at `player_pos` ends up running from the boss
+pub fn fear_destination(player_pos: Vec3, boss_pos: Vec3) -> Vec3 {
+    let away = (player_pos - boss_pos).truncate().normalize_or(Vec2::NEG_Y);
+    player_pos + (away * FEAR_DISTANCE).extend(0.)
+}
+
+pub fn fears_system(
+    mut commands: Commands,
+    fears: Query<(Entity, &Aoe), With<Fear>>,
+    bosses: Query<&Transform, With<Boss>>,
+    mut players: Query<(Entity, &Transform, &mut Player), Without<Downed>>,
+) {
+    for (entity_fear, aoe) in &fears {
+        if !aoe.detonation.just_finished() {
+            continue;
+        }
+        commands.entity(entity_fear).despawn_recursive();
+
+        let Ok(transform_boss) = bosses.get_single() else {
+            continue;
+        };
+
+        for (entity_player, transform_player, mut player) in &mut players {
+            // Dodging through it works the same as for any other hit
+            if !player.invuln.finished() || player.boons.consume(Boon::Stability) {
+                continue;
+            }
+
+            let target = fear_destination(transform_player.translation, transform_boss.translation);
+            commands.entity(entity_player).insert(EffectForcedMarch {
+                target,
+                speed: FEAR_SPEED,
+            });
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+
+use crate::aoes::Soup;
+use crate::collisions::{CollisionRadius, CollisionShape};
+use crate::game::{PhaseEntity, LAYER_ROTATING_SOUP};
+use crate::ledger::DamageSource;
+use crate::timeline::FrostBeamSpawn;
+
+/// How long before a beam starts moving that it and its path are shown
+pub const FROST_BEAM_TELEGRAPH_S: f32 = 2.;
+const FROST_BEAM_TELEGRAPH_ALPHA: f32 = 0.15;
+const FROST_BEAM_ACTIVE_ALPHA: f32 = 0.5;
+
+/// One leg of the path a beam is going to take, shown while it's telegraphed
+#[derive(Component)]
+pub struct FrostBeamPath {
+    pub beam: Entity,
+}
+
+#[derive(Component)]
+pub struct FrostBeam {
+    pub start: Timer,
+    pub path: Vec<Vec3>,
+    pub next_waypoint: usize,
+    pub speed: f32,
+    pub damage: f32,
+}
+
+impl FrostBeam {
+    pub fn is_telegraphed(&self) -> bool {
+        self.start.remaining_secs() < FROST_BEAM_TELEGRAPH_S && !self.is_done()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_waypoint >= self.path.len()
+    }
+
+    /// Where a beam currently at `pos` will be `duration` seconds from now
+    pub fn predict(&self, pos: Vec3, duration: f32) -> Vec3 {
+        let moving_duration = duration - self.start.remaining_secs();
+        let mut remaining = self.speed * moving_duration.max(0.);
+        let mut pos = pos;
+        for waypoint in self.path.iter().skip(self.next_waypoint) {
+            let to_waypoint = *waypoint - pos;
+            let len = to_waypoint.truncate().length();
+            if len >= remaining {
+                return pos + to_waypoint.clamp_length_max(remaining);
+            }
+            remaining -= len;
+            pos = *waypoint;
+        }
+        pos
+    }
+
+    /// Moves on from every waypoint the beam at `pos` has reached
+    pub fn pass_waypoints(&mut self, pos: Vec3) {
+        while let Some(waypoint) = self.path.get(self.next_waypoint) {
+            if pos.truncate().distance(waypoint.truncate()) > 0.01 {
+                break;
+            }
+            self.next_waypoint += 1;
+        }
+    }
+}
+
+pub fn setup_frost_beams(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    beams: Vec<FrostBeamSpawn>,
+) {
+    for beam in beams {
+        let path: Vec<Vec3> = beam
+            .path
+            .iter()
+            .map(|pos| pos.extend(LAYER_ROTATING_SOUP))
+            .collect();
+        let Some(&start_pos) = path.first() else {
+            continue;
+        };
+        let radius = beam.width / 2.;
+
+        let entity_beam = commands
+            .spawn((
+                Mesh2d(meshes.add(Circle::new(radius))),
+                MeshMaterial2d(
+                    materials.add(ColorMaterial::from(Color::srgba(0.6, 0.85, 1.0, 0.))),
+                ),
+                Transform::from_translation(start_pos),
+                FrostBeam {
+                    start: Timer::from_seconds(beam.start, TimerMode::Once),
+                    path: path.clone(),
+                    next_waypoint: 1,
+                    speed: beam.speed,
+                    damage: beam.damage,
+                },
+                CollisionRadius(radius),
+                Soup {
+                    damage: 0.,
+                    duration: None,
+                },
+                DamageSource("frost beam"),
+                PhaseEntity,
+            ))
+            .id();
+
+        let path_material = materials.add(ColorMaterial::from(Color::srgba(
+            0.6,
+            0.85,
+            1.0,
+            FROST_BEAM_TELEGRAPH_ALPHA,
+        )));
+        for leg in path.windows(2) {
+            let diff = (leg[1] - leg[0]).truncate();
+            let shape = CollisionShape::Capsule {
+                half_length: diff.length() / 2.,
+                radius,
+            };
+            let mid = (leg[0] + leg[1]) / 2.;
+            commands.spawn((
+                Mesh2d(meshes.add(shape.mesh())),
+                MeshMaterial2d(path_material.clone()),
+                Transform::from_translation(mid.with_z(LAYER_ROTATING_SOUP - 0.1)).with_rotation(
+                    Quat::from_rotation_arc_2d(Vec2::Y, diff.normalize_or(Vec2::Y)),
+                ),
+                Visibility::Hidden,
+                FrostBeamPath { beam: entity_beam },
+                PhaseEntity,
+            ));
+        }
+    }
+}
+
+pub fn frost_beam_paths_system(
+    mut commands: Commands,
+    beams: Query<&FrostBeam>,
+    mut paths: Query<(Entity, &FrostBeamPath, &mut Visibility)>,
+) {
+    for (entity_path, path, mut visibility) in &mut paths {
+        let Ok(beam) = beams.get(path.beam) else {
+            commands.entity(entity_path).despawn_recursive();
+            continue;
+        };
+        if beam.is_done() {
+            commands.entity(entity_path).despawn_recursive();
+            continue;
+        }
+        *visibility = if beam.is_telegraphed() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn frost_beams_system(
+    time: Res<Time>,
+    mut beams: Query<(
+        &mut FrostBeam,
+        &mut Soup,
+        &mut Transform,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (mut beam, mut soup, mut transform, material) in &mut beams {
+        beam.start.tick(time.delta());
+
+        let alpha = if beam.start.finished() {
+            FROST_BEAM_ACTIVE_ALPHA
+        } else if beam.is_telegraphed() {
+            FROST_BEAM_TELEGRAPH_ALPHA
+        } else {
+            0.
+        };
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_alpha(alpha);
+        }
+
+        if !beam.start.finished() {
+            continue;
+        }
+
+        soup.damage = beam.damage;
+        transform.translation = beam.predict(transform.translation, time.delta_secs());
+        beam.pass_waypoints(transform.translation);
+    }
+}
+
+pub fn frost_beams_despawn_system(mut commands: Commands, beams: Query<(Entity, &FrostBeam)>) {
+    for (entity, beam) in &beams {
+        if beam.is_done() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
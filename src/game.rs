@@ -1,10 +1,18 @@
 use bevy::{prelude::*, time::Stopwatch};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
 use std::time::Duration;
 
-use crate::{ai::AiRole, JUMP_DURATION_S};
+use crate::{
+    ai::AiRole,
+    boons::{Boon, Boons, PROTECTION_DAMAGE_MULTIPLIER},
+    ledger::{DamageLedger, Hit},
+    timeline::Mechanic,
+    JUMP_DURATION_S,
+};
 
-#[derive(States, Clone, Copy, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(States, Clone, Copy, Eq, PartialEq, Debug, Hash, Default, Serialize, Deserialize)]
 pub enum GameState {
     #[default]
     Nothing,
@@ -26,6 +34,7 @@ pub enum MenuState {
     #[default]
     Loading,
     StartMenu,
+    Controls,
     Failure,
     Success,
     Paused,
@@ -78,8 +87,27 @@ pub const CRAB_SIZE: f32 = 40.;
 #[derive(Component)]
 pub struct PhaseEntity;
 
+/// Marks where a local human is aiming, one per [`HumanPlayer`] index
 #[derive(Component)]
-pub struct CursorMark;
+pub struct CursorMark(pub usize);
+
+/// How many humans can play on one machine
+pub const LOCAL_PLAYERS: usize = 2;
+
+/// A human controlled player. The first [`LOCAL_PLAYERS`] indices are on this
+/// machine, keyboard and mouse then the first gamepad, and any after that are
+/// joined over the network
+#[derive(Component, Clone, Copy, Debug)]
+pub struct HumanPlayer {
+    pub index: usize,
+    pub role: AiRole,
+}
+
+impl HumanPlayer {
+    pub fn is_local(&self) -> bool {
+        self.index < LOCAL_PLAYERS
+    }
+}
 
 #[derive(Component)]
 pub struct Player {
@@ -92,19 +120,49 @@ pub struct Player {
     pub portal_cooldown: Timer,
     pub pull_cooldown: Timer,
     pub jump_cooldown: Timer,
+    /// Cooldown of the role skill from the human player's kit
+    pub utility_cooldown: Timer,
     pub invuln: Timer,
     pub jump: Timer,
+    pub last_damage_reason: Option<&'static str>,
+    pub reviving: bool,
+    pub boons: Boons,
+    hits: Vec<Hit>,
 }
 
 impl Player {
     pub fn new(name: String) -> Player {
         Player { name, ..default() }
     }
-    pub fn damage(&mut self, amount: f32, reason: &str) {
+    /// Returns the damage left after protection
+    pub fn damage(&mut self, amount: f32, mechanic: &'static str, source: &'static str) -> f32 {
+        let amount = if self.boons.has(Boon::Protection) {
+            amount * PROTECTION_DAMAGE_MULTIPLIER
+        } else {
+            amount
+        };
+        // Instakills only count for the hp the player actually had left
+        let dealt = amount.min(self.hp.max(0.));
+        if dealt > 0. {
+            self.hits.push(Hit {
+                mechanic,
+                source,
+                amount: dealt,
+            });
+        }
         self.hp -= amount;
+        self.last_damage_reason = Some(mechanic);
         if self.hp <= 0. {
-            info!("{} died: {}", self.name, reason);
+            info!("{} died: {}", self.name, mechanic);
         }
+        amount
+    }
+    /// Whether aegis soaks up a hit that would otherwise land
+    pub fn block(&mut self) -> bool {
+        self.boons.consume(Boon::Aegis)
+    }
+    pub fn take_hits(&mut self) -> Vec<Hit> {
+        std::mem::take(&mut self.hits)
     }
     pub fn heal(&mut self, amount: f32) {
         self.hp += amount;
@@ -115,6 +173,16 @@ impl Player {
     pub fn get_hp(&self) -> f32 {
         self.hp
     }
+    pub fn set_hp(&mut self, hp: f32) {
+        self.hp = hp.min(100.);
+    }
+}
+
+#[derive(Event)]
+pub struct PlayerDeathEvent {
+    pub player: Entity,
+    pub reason: Option<&'static str>,
+    pub damage_taken: f32,
 }
 
 #[derive(Component)]
@@ -142,14 +210,20 @@ impl Default for Player {
             portal_cooldown: Timer::from_seconds(60., TimerMode::Once),
             jump_cooldown: Timer::from_seconds(JUMP_DURATION_S, TimerMode::Once),
             pull_cooldown: Timer::from_seconds(20., TimerMode::Once),
+            utility_cooldown: Timer::from_seconds(20., TimerMode::Once),
             invuln: Timer::from_seconds(0.75, TimerMode::Once),
             jump: Timer::from_seconds(0.75, TimerMode::Once),
+            last_damage_reason: None,
+            reviving: false,
+            boons: Boons::default(),
+            hits: vec![],
         };
 
         player.dodge_cooldown.tick(Duration::from_secs_f32(1000.));
         player.blink_cooldown.tick(Duration::from_secs_f32(1000.));
         player.portal_cooldown.tick(Duration::from_secs_f32(1000.));
         player.pull_cooldown.tick(Duration::from_secs_f32(1000.));
+        player.utility_cooldown.tick(Duration::from_secs_f32(1000.));
         player.invuln.tick(Duration::from_secs_f32(1000.));
         player.jump.tick(Duration::from_secs_f32(1000.));
 
@@ -157,10 +231,95 @@ impl Default for Player {
     }
 }
 
+/// How hard the fight is. Every constant elsewhere is tuned for challenge
+/// mode, the others scale it down
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    Story,
+    Normal,
+    #[default]
+    ChallengeMode,
+}
+
+impl Difficulty {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Story => "Story",
+            Difficulty::Normal => "Normal",
+            Difficulty::ChallengeMode => "Challenge Mode",
+        }
+    }
+
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Story => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::ChallengeMode,
+            Difficulty::ChallengeMode => Difficulty::Story,
+        }
+    }
+
+    /// Damage a player takes from a mechanic that deals `damage` in CM
+    pub fn mechanic_damage(&self, damage: f32) -> f32 {
+        match self {
+            Difficulty::Story => damage * 0.4,
+            Difficulty::Normal => damage * 0.7,
+            Difficulty::ChallengeMode => damage,
+        }
+    }
+
+    /// Failing a green only wipes the squad in CM
+    pub fn green_damage(&self) -> f32 {
+        match self {
+            Difficulty::Story => 40.,
+            Difficulty::Normal => 80.,
+            Difficulty::ChallengeMode => 999.,
+        }
+    }
+
+    pub fn boss_hp(&self, hp: f32) -> f32 {
+        match self {
+            Difficulty::Story => hp * 0.5,
+            Difficulty::Normal => hp * 0.75,
+            Difficulty::ChallengeMode => hp,
+        }
+    }
+
+    /// Seconds between attacks for a mob that attacks every `secs` in CM
+    pub fn mob_cooldown(&self, secs: f32) -> f32 {
+        match self {
+            Difficulty::Story => secs * 2.,
+            Difficulty::Normal => secs * 1.4,
+            Difficulty::ChallengeMode => secs,
+        }
+    }
+
+    pub fn has_mechanic(&self, mechanic: &Mechanic) -> bool {
+        match mechanic {
+            Mechanic::Wyvern { .. } => *self == Difficulty::ChallengeMode,
+            Mechanic::Goliath { .. } => *self != Difficulty::Story,
+            _ => true,
+        }
+    }
+}
+
+/// Speeds the "Game Speed" button cycles through, 1 being real time
+pub const GAME_SPEEDS: [f32; 4] = [0.5, 0.75, 1., 1.25];
+
+/// Snaps a speed read from settings or a replay to one of [`GAME_SPEEDS`]
+pub fn closest_game_speed(speed: f32) -> f32 {
+    if !speed.is_finite() {
+        return 1.;
+    }
+    GAME_SPEEDS
+        .into_iter()
+        .min_by(|a, b| (a - speed).abs().total_cmp(&(b - speed).abs()))
+        .unwrap_or(1.)
+}
+
 #[derive(Resource)]
 pub struct Game {
     pub time_elapsed: Stopwatch,
-    pub player_damage_taken: f32,
+    pub damage_ledger: DamageLedger,
     pub orb_target: i32,
     pub continuous: bool,
     pub echo_enabled: bool,
@@ -172,7 +331,100 @@ pub struct Game {
     pub greens_enabled: bool,
     pub unlimited_range_enabled: bool,
     pub player_role: Option<AiRole>,
+    /// Role of a second human on a gamepad, if one is playing along
+    pub second_player_role: Option<AiRole>,
+    /// Roles of humans joined over the network by slot, None once they've
+    /// left. A slot keeps its [`HumanPlayer`] index for the whole run
+    pub remote_player_roles: Vec<Option<AiRole>>,
     pub audio_enabled: bool,
+    /// Seconds into a single phase to start at, for practicing late mechanics
+    pub start_time: f32,
+    pub camera_follow_enabled: bool,
+    /// How far the following camera is zoomed in, 1 shows the whole arena
+    pub camera_zoom: f32,
+    pub difficulty: Difficulty,
+    /// How fast the phase runs compared to real time
+    pub game_speed: f32,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game {
+            time_elapsed: Stopwatch::new(),
+            damage_ledger: DamageLedger::default(),
+            continuous: false,
+            orb_target: -1,
+            echo_enabled: false,
+            hints_enabled: true,
+            hint: None,
+            greens_enabled: true,
+            puddles_enabled: true,
+            unlimited_range_enabled: true,
+            ai_enabled: true,
+            ai_bars_enabled: true,
+            player_role: Some(AiRole::Virt1),
+            second_player_role: None,
+            remote_player_roles: vec![],
+            audio_enabled: true,
+            start_time: 0.,
+            camera_follow_enabled: false,
+            camera_zoom: 2.,
+            difficulty: Difficulty::default(),
+            game_speed: 1.,
+        }
+    }
+}
+
+impl Game {
+    /// Roles of the humans on this machine with their [`HumanPlayer`] index
+    pub fn local_player_roles(&self) -> Vec<(usize, AiRole)> {
+        [self.player_role, self.second_player_role]
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, role)| role.map(|role| (index, role)))
+            .collect()
+    }
+
+    /// Roles of every human in the squad, local or remote, with their
+    /// [`HumanPlayer`] index
+    pub fn human_player_roles(&self) -> Vec<(usize, AiRole)> {
+        let mut roles = self.local_player_roles();
+        for (i, role) in self.remote_player_roles.iter().enumerate() {
+            if let Some(role) = role {
+                roles.push((LOCAL_PLAYERS + i, *role));
+            }
+        }
+        roles
+    }
+
+    /// Shown next to results that weren't played at full speed
+    pub fn game_speed_label(&self) -> Option<String> {
+        if self.game_speed == 1. {
+            return None;
+        }
+        let percent = (self.game_speed * 100.).round();
+        if self.game_speed < 1. {
+            Some(format!("slowed to {}%", percent))
+        } else {
+            Some(format!("sped up to {}%", percent))
+        }
+    }
+}
+
+/// Shared source of randomness for phase systems so that runs can be replayed
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> GameRng {
+        GameRng(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(StdRng::from_entropy())
+    }
 }
 
 pub fn next_game_state(game_state: GameState) -> GameState {
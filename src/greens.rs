@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 use std::ops::Add;
 
 use crate::game::*;
@@ -9,133 +10,12 @@ use crate::{
 
 pub const GREEN_RADIUS: f32 = 160. * GAME_TO_PX;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Deserialize)]
 pub struct GreenSpawn {
     start: f32,
     positions: [Vec3; 3],
 }
 
-pub const GREEN_SPAWNS_JORMAG: [GreenSpawn; 2] = [
-    GreenSpawn {
-        start: 15.,
-        positions: [
-            Vec3::new(-270., 0., 0.),
-            Vec3::new(-78., 240., 0.),
-            Vec3::new(269., 3., 0.),
-        ],
-    },
-    GreenSpawn {
-        start: 55.,
-        positions: [
-            Vec3::new(-303., 1., 0.),
-            Vec3::new(-78., 299., 0.),
-            Vec3::new(312., 3., 0.),
-        ],
-    },
-];
-
-pub const GREEN_SPAWNS_PRIMORDUS: [GreenSpawn; 2] = [
-    GreenSpawn {
-        start: 23.,
-        positions: [
-            Vec3::new(-274., -113., 0.),
-            Vec3::new(-62., -290., 0.),
-            Vec3::new(269., -111., 0.),
-        ],
-    },
-    GreenSpawn {
-        start: 77.,
-        positions: [
-            Vec3::new(-364., -155., 0.),
-            Vec3::new(-82., -387., 0.),
-            Vec3::new(365., -153., 0.),
-        ],
-    },
-];
-
-pub const GREEN_SPAWNS_ZHAITAN: [GreenSpawn; 3] = [
-    GreenSpawn {
-        start: 0., // actually -5., not entirely sure what to do here
-        positions: [
-            Vec3::new(-158., -114., 0.),
-            Vec3::new(1., 258., 0.),
-            Vec3::new(158., -110., 0.),
-        ],
-    },
-    GreenSpawn {
-        start: 28. + 5.,
-        positions: [
-            Vec3::new(-201., -131., 0.),
-            Vec3::new(1., 258., 0.),
-            Vec3::new(197., -131., 0.),
-        ],
-    },
-    GreenSpawn {
-        start: 60. + 5.,
-        positions: [
-            Vec3::new(-308., -189., 0.),
-            Vec3::new(2., 387., 0.),
-            Vec3::new(308., -179., 0.),
-        ],
-    },
-];
-
-pub const GREEN_SPAWNS_SOOWONONE: [GreenSpawn; 2] = [
-    GreenSpawn {
-        start: 5.,
-        positions: [
-            Vec3::new(-199., -64., 0.),
-            Vec3::new(-131., 75., 0.),
-            Vec3::new(-47., 351., 0.),
-        ],
-    },
-    GreenSpawn {
-        start: 50.,
-        positions: [
-            Vec3::new(-290., -101., 0.),
-            Vec3::new(-268., 174., 0.),
-            Vec3::new(-47., 351., 0.),
-        ],
-    }, // there's another at 90 :(
-];
-
-pub const GREEN_SPAWNS_SOOWONTWO: [GreenSpawn; 3] = [
-    GreenSpawn {
-        start: 10.7,
-        positions: [
-            Vec3::new(-199., -64., 0.),
-            Vec3::new(-131., 75., 0.),
-            Vec3::new(-47., 351., 0.),
-        ],
-    },
-    GreenSpawn {
-        start: 52.,
-        positions: [
-            Vec3::new(-290., -101., 0.),
-            Vec3::new(-268., 174., 0.),
-            Vec3::new(-47., 351., 0.),
-        ],
-    },
-    GreenSpawn {
-        start: 103.,
-        positions: [
-            Vec3::new(-30., WIDTH / 2. - GREEN_RADIUS * 1.2, 0.),
-            {
-                let r = WIDTH / 2. - GREEN_RADIUS * 1.2;
-                let cos = -0.924;
-                let sin = 0.383;
-                Vec3::new(r * cos, r * sin, 0.)
-            },
-            {
-                let r = WIDTH / 2. - GREEN_RADIUS * 1.2;
-                let cos = -0.809;
-                let sin = -0.588;
-                Vec3::new(r * cos, r * sin, 0.)
-            },
-        ],
-    },
-];
-
 #[derive(Component)]
 pub struct StackGreen {
     pub visibility_start: Timer,
@@ -216,7 +96,7 @@ pub fn greens_detonation_system(
             if !any_collide {
                 if game.greens_enabled {
                     for (mut player, _) in &mut players {
-                        player.damage(999., "green detonation");
+                        player.damage(game.difficulty.green_damage(), "green", "green");
                     }
                 }
                 info!("green exploded");
@@ -4,8 +4,8 @@ use crate::game::*;
 
 #[derive(Component)]
 pub struct ScheduledHint {
-    start: Timer,
-    hint: &'static str,
+    pub start: Timer,
+    pub hint: &'static str,
 }
 
 pub struct HintTiming {
@@ -81,10 +81,16 @@ fn phase_hints(phase: &GameState) -> Vec<HintTiming> {
             }]
         }
         GameState::Zhaitan => {
-            vec![HintTiming {
-                start: 5.5,
-                hint: "Noodles attack the area near them. Kill them before they kill you!",
-            }]
+            vec![
+                HintTiming {
+                    start: 5.5,
+                    hint: "Noodles attack the area near them. Kill them before they kill you!",
+                },
+                HintTiming {
+                    start: 19.5,
+                    hint: "Zhaitan's scream fears everyone into running away from the boss. Dodge as it goes off, or stand close enough that the run doesn't take you off the edge or into a puddle.",
+                },
+            ]
         }
         GameState::PurificationThree => {
             vec![HintTiming {
@@ -93,10 +99,16 @@ fn phase_hints(phase: &GameState) -> Vec<HintTiming> {
             }]
         }
         GameState::SooWonOne => {
-            vec![HintTiming {
-                start: 1.,
-                hint: "Get ready for everything from every phase all at once!",
-            }]
+            vec![
+                HintTiming {
+                    start: 1.,
+                    hint: "Get ready for everything from every phase all at once!",
+                },
+                HintTiming {
+                    start: 31.,
+                    hint: "Soo-Won is about to swap sides. Her claw swipes follow her, so move the stack with her.",
+                },
+            ]
         }
         GameState::PurificationFour => {
             vec![HintTiming {
@@ -0,0 +1,452 @@
+use bevy::{
+    prelude::*,
+    window::{CursorMoved, PrimaryWindow},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use crate::camera::WorldCamera;
+use crate::game::{Game, HumanPlayer, GAME_TO_PX, LOCAL_PLAYERS};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Shoot,
+    Jump,
+    Dodge,
+    Blink,
+    Pull,
+    Portal,
+    TakePortal,
+    Revive,
+    Utility,
+    ZoomIn,
+    ZoomOut,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 15] = [
+        InputAction::MoveUp,
+        InputAction::MoveDown,
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::Shoot,
+        InputAction::Jump,
+        InputAction::Dodge,
+        InputAction::Blink,
+        InputAction::Pull,
+        InputAction::Portal,
+        InputAction::TakePortal,
+        InputAction::Revive,
+        InputAction::Utility,
+        InputAction::ZoomIn,
+        InputAction::ZoomOut,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::MoveUp => "Move Up",
+            InputAction::MoveDown => "Move Down",
+            InputAction::MoveLeft => "Move Left",
+            InputAction::MoveRight => "Move Right",
+            InputAction::Shoot => "Shoot",
+            InputAction::Jump => "Jump",
+            InputAction::Dodge => "Dodge",
+            InputAction::Blink => "Blink",
+            InputAction::Pull => "Pull",
+            InputAction::Portal => "Portal",
+            InputAction::TakePortal => "Take Portal",
+            InputAction::Revive => "Revive",
+            InputAction::Utility => "Role Skill",
+            InputAction::ZoomIn => "Zoom In",
+            InputAction::ZoomOut => "Zoom Out",
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl InputBinding {
+    /// Short name for menus and the skill bar, e.g. "E", "1" or "LMB"
+    pub fn label(&self) -> String {
+        match self {
+            InputBinding::Key(key) => {
+                let name = format!("{key:?}");
+                ["Key", "Digit", "Arrow"]
+                    .iter()
+                    .find_map(|prefix| name.strip_prefix(prefix))
+                    .filter(|rest| !rest.is_empty())
+                    .unwrap_or(name.as_str())
+                    .to_string()
+            }
+            InputBinding::Mouse(MouseButton::Left) => "LMB".to_string(),
+            InputBinding::Mouse(MouseButton::Right) => "RMB".to_string(),
+            InputBinding::Mouse(MouseButton::Middle) => "MMB".to_string(),
+            InputBinding::Mouse(MouseButton::Back) => "M4".to_string(),
+            InputBinding::Mouse(MouseButton::Forward) => "M5".to_string(),
+            InputBinding::Mouse(MouseButton::Other(button)) => format!("M{button}"),
+        }
+    }
+}
+
+/// How many bindings each action can have at once
+pub const BINDING_SLOTS: usize = 2;
+
+/// Stick deflection below this is ignored
+const GAMEPAD_STICK_DEADZONE: f32 = 0.2;
+/// How far from the player the aim cursor sits with the right stick fully
+/// tilted, enough to reach full blink range
+const GAMEPAD_AIM_RANGE: f32 = 1200.0 * GAME_TO_PX;
+
+/// Which keys and mouse buttons trigger each action
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct InputBindings {
+    pub slots: HashMap<InputAction, [Option<InputBinding>; BINDING_SLOTS]>,
+    #[serde(default = "default_gamepad_bindings")]
+    pub gamepad: HashMap<InputAction, GamepadButton>,
+}
+
+fn default_gamepad_bindings() -> HashMap<InputAction, GamepadButton> {
+    HashMap::from([
+        (InputAction::MoveUp, GamepadButton::DPadUp),
+        (InputAction::MoveDown, GamepadButton::DPadDown),
+        (InputAction::MoveLeft, GamepadButton::DPadLeft),
+        (InputAction::MoveRight, GamepadButton::DPadRight),
+        (InputAction::Shoot, GamepadButton::RightTrigger2),
+        (InputAction::Jump, GamepadButton::South),
+        (InputAction::Dodge, GamepadButton::East),
+        (InputAction::Blink, GamepadButton::LeftTrigger2),
+        (InputAction::Pull, GamepadButton::RightTrigger),
+        (InputAction::Portal, GamepadButton::North),
+        (InputAction::TakePortal, GamepadButton::LeftTrigger),
+        (InputAction::Revive, GamepadButton::West),
+        (InputAction::Utility, GamepadButton::LeftThumb),
+    ])
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use InputBinding::{Key, Mouse};
+
+        let slots = HashMap::from([
+            (
+                InputAction::MoveUp,
+                [Some(Key(KeyCode::KeyW)), Some(Key(KeyCode::ArrowUp))],
+            ),
+            (
+                InputAction::MoveDown,
+                [Some(Key(KeyCode::KeyS)), Some(Key(KeyCode::ArrowDown))],
+            ),
+            (
+                InputAction::MoveLeft,
+                [Some(Key(KeyCode::KeyA)), Some(Key(KeyCode::ArrowLeft))],
+            ),
+            (
+                InputAction::MoveRight,
+                [Some(Key(KeyCode::KeyD)), Some(Key(KeyCode::ArrowRight))],
+            ),
+            (
+                InputAction::Shoot,
+                [Some(Key(KeyCode::Digit1)), Some(Mouse(MouseButton::Left))],
+            ),
+            (InputAction::Jump, [Some(Key(KeyCode::Space)), None]),
+            (InputAction::Dodge, [Some(Key(KeyCode::KeyV)), None]),
+            (InputAction::Blink, [Some(Key(KeyCode::KeyE)), None]),
+            (InputAction::Pull, [Some(Key(KeyCode::Digit4)), None]),
+            (InputAction::Portal, [Some(Key(KeyCode::KeyR)), None]),
+            (InputAction::TakePortal, [Some(Key(KeyCode::KeyF)), None]),
+            (InputAction::Revive, [Some(Key(KeyCode::KeyG)), None]),
+            (InputAction::Utility, [Some(Key(KeyCode::KeyQ)), None]),
+            (InputAction::ZoomIn, [Some(Key(KeyCode::Equal)), None]),
+            (InputAction::ZoomOut, [Some(Key(KeyCode::Minus)), None]),
+        ]);
+        InputBindings {
+            slots,
+            gamepad: default_gamepad_bindings(),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn get(&self, action: InputAction, slot: usize) -> Option<InputBinding> {
+        self.slots
+            .get(&action)
+            .and_then(|bindings| bindings.get(slot).copied().flatten())
+    }
+
+    /// Gives actions added since these bindings were saved their default
+    /// bindings, as long as those aren't already taken
+    pub fn add_missing_defaults(&mut self) {
+        let defaults = InputBindings::default();
+        for action in InputAction::ALL {
+            if self.slots.contains_key(&action) {
+                continue;
+            }
+            let mut bindings = defaults.slots[&action];
+            for binding in bindings.iter_mut() {
+                if self
+                    .slots
+                    .values()
+                    .flatten()
+                    .any(|existing| existing == binding)
+                {
+                    *binding = None;
+                }
+            }
+            self.slots.insert(action, bindings);
+        }
+        for (action, button) in defaults.gamepad {
+            if !self.gamepad.values().any(|existing| *existing == button) {
+                self.gamepad.entry(action).or_insert(button);
+            }
+        }
+    }
+
+    /// Short name of the gamepad button bound to `action`, e.g. "RT"
+    pub fn gamepad_label(&self, action: InputAction) -> String {
+        let Some(button) = self.gamepad.get(&action) else {
+            return String::new();
+        };
+        match button {
+            GamepadButton::South => "A",
+            GamepadButton::East => "B",
+            GamepadButton::West => "X",
+            GamepadButton::North => "Y",
+            GamepadButton::LeftTrigger => "LB",
+            GamepadButton::LeftTrigger2 => "LT",
+            GamepadButton::RightTrigger => "RB",
+            GamepadButton::RightTrigger2 => "RT",
+            GamepadButton::LeftThumb => "LS",
+            GamepadButton::RightThumb => "RS",
+            GamepadButton::DPadUp => "Up",
+            GamepadButton::DPadDown => "Down",
+            GamepadButton::DPadLeft => "Left",
+            GamepadButton::DPadRight => "Right",
+            other => return format!("{other:?}"),
+        }
+        .to_string()
+    }
+
+    /// Binds `binding` to `action`, taking it away from whatever had it before
+    pub fn set(&mut self, action: InputAction, slot: usize, binding: Option<InputBinding>) {
+        if binding.is_some() {
+            for bindings in self.slots.values_mut() {
+                for existing in bindings.iter_mut() {
+                    if *existing == binding {
+                        *existing = None;
+                    }
+                }
+            }
+        }
+        self.slots.entry(action).or_default()[slot] = binding;
+    }
+
+    /// Whether any key or mouse button bound to `action` went down this frame
+    pub fn just_pressed(
+        &self,
+        action: InputAction,
+        keyboard_input: &ButtonInput<KeyCode>,
+        mouse_button_input: &ButtonInput<MouseButton>,
+    ) -> bool {
+        (0..BINDING_SLOTS).any(|slot| match self.get(action, slot) {
+            Some(InputBinding::Key(key)) => keyboard_input.just_pressed(key),
+            Some(InputBinding::Mouse(button)) => mouse_button_input.just_pressed(button),
+            None => false,
+        })
+    }
+
+    /// Every binding of `action` joined up, e.g. "1 / LMB"
+    pub fn label(&self, action: InputAction) -> String {
+        (0..BINDING_SLOTS)
+            .filter_map(|slot| self.get(action, slot))
+            .map(|binding| binding.label())
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+/// Everything a human player did this frame, in terms of game actions
+/// instead of physical keys
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct HumanInput {
+    pub cursor: Vec2,
+    /// Analog movement from a gamepad stick, used when no movement keys are held
+    #[serde(default)]
+    pub stick: Vec2,
+    pub pressed: HashSet<InputAction>,
+    pub just_pressed: HashSet<InputAction>,
+}
+
+impl HumanInput {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn movement(&self) -> Vec2 {
+        let mut movement = Vec2::ZERO;
+        if self.pressed(InputAction::MoveUp) {
+            movement.y += 1.;
+        }
+        if self.pressed(InputAction::MoveDown) {
+            movement.y -= 1.;
+        }
+        if self.pressed(InputAction::MoveLeft) {
+            movement.x -= 1.;
+        }
+        if self.pressed(InputAction::MoveRight) {
+            movement.x += 1.;
+        }
+        if movement == Vec2::ZERO {
+            return self.stick.clamp_length_max(1.);
+        }
+        movement
+    }
+}
+
+/// This frame's input for every human, indexed by [`HumanPlayer`]. Local
+/// humans come first, then anyone joined over the network
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct HumanInputs(pub Vec<HumanInput>);
+
+impl Default for HumanInputs {
+    fn default() -> Self {
+        HumanInputs(vec![HumanInput::default(); LOCAL_PLAYERS])
+    }
+}
+
+/// Stands in for a human with no input recorded this frame
+static NO_INPUT: LazyLock<HumanInput> = LazyLock::new(HumanInput::default);
+
+impl HumanInputs {
+    pub fn get(&self, human_player: &HumanPlayer) -> &HumanInput {
+        self.slot(human_player.index)
+    }
+
+    pub fn slot(&self, index: usize) -> &HumanInput {
+        self.0.get(index).unwrap_or(&NO_INPUT)
+    }
+
+    /// Makes room for `count` humans in total
+    pub fn ensure_len(&mut self, count: usize) {
+        if self.0.len() < count {
+            self.0.resize(count, HumanInput::default());
+        }
+    }
+}
+
+pub fn read_human_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    bindings: Res<InputBindings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
+    mut inputs: ResMut<HumanInputs>,
+) {
+    inputs.ensure_len(LOCAL_PLAYERS);
+    for input in inputs.0.iter_mut() {
+        input.pressed.clear();
+        input.just_pressed.clear();
+    }
+    let input = &mut inputs.0[0];
+
+    for (&action, action_bindings) in &bindings.slots {
+        for binding in action_bindings.iter().flatten() {
+            let (pressed, just_pressed) = match *binding {
+                InputBinding::Key(key) => (
+                    keyboard_input.pressed(key),
+                    keyboard_input.just_pressed(key),
+                ),
+                InputBinding::Mouse(button) => (
+                    mouse_button_input.pressed(button),
+                    mouse_button_input.just_pressed(button),
+                ),
+            };
+            if pressed {
+                input.pressed.insert(action);
+            }
+            if just_pressed {
+                input.just_pressed.insert(action);
+            }
+        }
+    }
+
+    // Converted every frame rather than on mouse movement since the camera
+    // may have moved underneath a still cursor
+    let Some(cursor_position) = windows.get_single().ok().and_then(Window::cursor_position) else {
+        return;
+    };
+    if let Ok((camera, transform_camera)) = cameras.get_single() {
+        if let Ok(cursor) = camera.viewport_to_world_2d(transform_camera, cursor_position) {
+            input.cursor = cursor;
+        }
+    }
+}
+
+/// Adds gamepad buttons and sticks on top of the keyboard and mouse. The right
+/// stick places the cursor around the player until the mouse moves again. With
+/// a second local player the first gamepad is theirs instead
+pub fn read_gamepad_input_system(
+    game: Res<Game>,
+    gamepads: Query<&Gamepad>,
+    bindings: Res<InputBindings>,
+    players: Query<(&Transform, &HumanPlayer)>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut inputs: ResMut<HumanInputs>,
+    mut aims: Local<[Option<Vec2>; LOCAL_PLAYERS]>,
+) {
+    inputs.ensure_len(LOCAL_PLAYERS);
+    for input in inputs.0.iter_mut() {
+        input.stick = Vec2::ZERO;
+    }
+
+    if cursor_moved_events.read().count() > 0 {
+        aims[0] = None;
+    }
+
+    let second_player = game.second_player_role.is_some();
+    for (i, gamepad) in gamepads.iter().enumerate() {
+        let index = if second_player && i == 0 { 1 } else { 0 };
+        let input = &mut inputs.0[index];
+        let aim = &mut aims[index];
+
+        for (&action, &button) in &bindings.gamepad {
+            if gamepad.pressed(button) {
+                input.pressed.insert(action);
+            }
+            if gamepad.just_pressed(button) {
+                input.just_pressed.insert(action);
+            }
+        }
+
+        let left_stick = gamepad.left_stick();
+        if left_stick.length() > GAMEPAD_STICK_DEADZONE {
+            input.stick = left_stick;
+        }
+
+        let right_stick = gamepad.right_stick();
+        if right_stick.length() > GAMEPAD_STICK_DEADZONE {
+            *aim = Some(right_stick.clamp_length_max(1.) * GAMEPAD_AIM_RANGE);
+        }
+    }
+
+    for (transform_player, human_player) in &players {
+        if !human_player.is_local() {
+            continue;
+        }
+        if let Some(aim) = aims[human_player.index] {
+            inputs.0[human_player.index].cursor = transform_player.translation.truncate() + aim;
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::game::{Game, Player};
+
+/// Continuous damage (soups, echo) from the same mechanic is merged into one
+/// entry as long as the gaps between ticks are shorter than this
+const LEDGER_MERGE_GAP: f32 = 0.25;
+
+/// Names the mechanic an entity's damage belongs to, e.g. "chomp" for a
+/// chomp aoe or "wyvern shot" for a wyvern bullet
+#[derive(Component, Clone, Copy)]
+pub struct DamageSource(pub &'static str);
+
+#[derive(Clone, Debug)]
+pub struct Hit {
+    pub mechanic: &'static str,
+    pub source: &'static str,
+    pub amount: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct LedgerEntry {
+    pub mechanic: &'static str,
+    pub source: &'static str,
+    pub time: f32,
+    pub duration: f32,
+    pub victim: String,
+    pub amount: f32,
+}
+
+/// Every bit of damage players took this run
+#[derive(Default)]
+pub struct DamageLedger {
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl DamageLedger {
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn record(&mut self, time: f32, victim: &str, hit: Hit) {
+        let previous = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.victim == victim);
+        if let Some(entry) = previous {
+            if entry.mechanic == hit.mechanic
+                && entry.source == hit.source
+                && time - (entry.time + entry.duration) < LEDGER_MERGE_GAP
+            {
+                entry.amount += hit.amount;
+                entry.duration = time - entry.time;
+                return;
+            }
+        }
+
+        self.entries.push(LedgerEntry {
+            mechanic: hit.mechanic,
+            source: hit.source,
+            time,
+            duration: 0.,
+            victim: victim.to_string(),
+            amount: hit.amount,
+        });
+    }
+
+    pub fn record_hits(&mut self, time: f32, player: &mut Player) {
+        for hit in player.take_hits() {
+            self.record(time, &player.name, hit);
+        }
+    }
+
+    /// Mechanics in the order they first dealt damage
+    pub fn mechanics(&self) -> Vec<&'static str> {
+        let mut mechanics = vec![];
+        for entry in &self.entries {
+            if !mechanics.contains(&entry.mechanic) {
+                mechanics.push(entry.mechanic);
+            }
+        }
+        mechanics
+    }
+
+    /// Players in the order they first took damage
+    pub fn victims(&self) -> Vec<&str> {
+        let mut victims = vec![];
+        for entry in &self.entries {
+            if !victims.contains(&entry.victim.as_str()) {
+                victims.push(entry.victim.as_str());
+            }
+        }
+        victims
+    }
+
+    pub fn total(&self, mechanic: Option<&str>, victim: Option<&str>) -> f32 {
+        self.entries
+            .iter()
+            .filter(|entry| mechanic.is_none_or(|mechanic| entry.mechanic == mechanic))
+            .filter(|entry| victim.is_none_or(|victim| entry.victim == victim))
+            .map(|entry| entry.amount)
+            .sum()
+    }
+}
+
+pub fn damage_ledger_system(mut game: ResMut<Game>, mut players: Query<&mut Player>) {
+    let time = game.time_elapsed.elapsed_secs();
+    for mut player in &mut players {
+        game.damage_ledger.record_hits(time, &mut player);
+    }
+}
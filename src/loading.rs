@@ -1,6 +1,7 @@
 use bevy::asset::LoadState;
 use bevy::prelude::*;
 
+use crate::timeline::load_phase_timelines;
 use crate::{MenuContainer, MenuState, HEIGHT, WIDTH};
 
 #[derive(Resource)]
@@ -76,6 +77,12 @@ pub fn setup_loading_system(
         loading.0.push(sound.untyped());
     }
 
+    let timelines = load_phase_timelines(&asset_server);
+    for timeline in timelines.0.values() {
+        loading.0.push(timeline.clone().untyped());
+    }
+    commands.insert_resource(timelines);
+
     commands
         .spawn((
             Node {
@@ -108,9 +115,24 @@ pub fn setup_loading_system(
 pub fn update_loading_system(
     asset_server: Res<AssetServer>,
     loading: Res<AssetsLoading>,
-    mut texts: Query<&mut Text, With<LoadingText>>,
+    mut texts: Query<(&mut Text, &mut TextFont), With<LoadingText>>,
     mut res_next_menu_state: ResMut<NextState<MenuState>>,
 ) {
+    let failed = loading
+        .0
+        .iter()
+        .find_map(|h| match asset_server.get_load_state(h.id()) {
+            Some(LoadState::Failed(err)) => Some(err),
+            _ => None,
+        });
+    if let Some(err) = failed {
+        for (mut text, mut font) in &mut texts {
+            font.font_size = 24.;
+            text.0 = format!("Failed to load: {}", err);
+        }
+        return;
+    }
+
     let loading_len = loading.0.len();
     let load_states = loading
         .0
@@ -133,7 +155,7 @@ pub fn update_loading_system(
         res_next_menu_state.set(MenuState::StartMenu);
         return;
     }
-    for mut text in &mut texts {
+    for (mut text, _) in &mut texts {
         text.0 = format!("Loading: {}/{}", loaded_count, loading_len);
     }
 }
@@ -1,6 +1,5 @@
-use ai::{player_ai_purification_phase_system, AiRole};
-use bevy::render::camera::CameraProjection;
-use bevy::{prelude::*, sprite::Anchor, time::Stopwatch, window::WindowResolution};
+use ai::player_ai_purification_phase_system;
+use bevy::{prelude::*, sprite::Anchor, window::WindowResolution};
 use core::f32::consts::PI;
 use loading::{setup_loading_system, update_loading_system, AssetsLoading};
 use rand::Rng;
@@ -10,32 +9,64 @@ use std::time::Duration;
 mod ai;
 mod aoes;
 mod audio;
+mod boons;
+mod boss_movement;
 mod boss_phase;
+mod camera;
+mod cast_bar;
 mod collisions;
 mod damage_flash;
+mod downed;
+mod fast_forward;
+mod fears;
+mod frost_beams;
 mod game;
 mod greens;
 mod hints;
+mod input;
+mod ledger;
 mod loading;
 mod menu;
 mod mobs;
+#[cfg(not(target_arch = "wasm32"))]
+mod net;
 mod orbs;
 mod phase;
 mod purification_phase;
+mod replay;
+mod roster;
+mod settings;
+#[cfg(not(target_arch = "wasm32"))]
+mod sim;
+mod skills;
+mod timeline;
+mod timeline_bar;
 mod ui;
 mod waves;
 
 use crate::aoes::*;
+use crate::boss_movement::*;
 use crate::boss_phase::*;
+use crate::camera::*;
+use crate::cast_bar::setup_cast_bar;
 use crate::collisions::*;
 use crate::damage_flash::*;
+use crate::fast_forward::*;
+use crate::fears::Fear;
+use crate::frost_beams::*;
 use crate::game::*;
 use crate::greens::*;
+use crate::input::InputBindings;
+use crate::ledger::*;
 use crate::menu::*;
 use crate::mobs::*;
 use crate::orbs::*;
 use crate::phase::*;
 use crate::purification_phase::*;
+use crate::replay::*;
+use crate::settings::load_settings;
+use crate::timeline::*;
+use crate::timeline_bar::setup_timeline_bar;
 use crate::ui::*;
 use crate::waves::*;
 
@@ -56,6 +87,13 @@ const ROTATING_SOUP_DTHETA: f32 = 0.2;
 
 const LASER_RADIUS: f32 = 25.;
 
+const LINE_RADIUS: f32 = BOSS_RADIUS * 0.9;
+const LINE_DELAY: f32 = 2.;
+const LINE_DURATION: f32 = 5.;
+/// Lines appear a segment at a time sweeping along their length
+const LINE_SEGMENTS: i32 = 8;
+const LINE_SEGMENT_LENGTH: f32 = GAME_WIDTH / LINE_SEGMENTS as f32;
+
 const SWIPE_CHONK_RADIUS: f32 = 650. * GAME_TO_PX;
 const SWIPE_CENTER: Vec3 = Vec3::new(-428. * GAME_TO_PX, 1061. * GAME_TO_PX, LAYER_WAVE);
 // not quiiite correct-looking
@@ -70,15 +108,18 @@ const SWIPE_BALL_COUNT: usize = 8;
 const SWIPE_DETONATION: f32 = 2.;
 const SWIPE_DAMAGE: f32 = 40.;
 
-fn setup(mut commands: Commands, mut players: Query<&mut Player>) {
-    let projection = OrthographicProjection {
-        far: LAYER_MAX,
-        ..OrthographicProjection::default_2d()
-    };
-    let transform = Transform::from_xyz(0., 0., LAYER_MAX - 0.1);
-    let frustum = projection.compute_frustum(&GlobalTransform::from(transform));
+const TAIL_SLAM_RADIUS: f32 = 520. * GAME_TO_PX;
+const TAIL_SLAM_DETONATION: f32 = 2.;
+const TAIL_SLAM_DAMAGE: f32 = 40.;
+const TAIL_SLAM_KNOCKBACK: f32 = 450. * GAME_TO_PX;
+const BITE_RADIUS: f32 = 1000. * GAME_TO_PX;
+const BITE_HALF_ANGLE: f32 = 0.55;
+const BITE_DETONATION: f32 = 1.5;
+const BITE_DAMAGE: f32 = 60.;
+const BITE_KNOCKBACK: f32 = 150. * GAME_TO_PX;
 
-    commands.spawn((Camera2d, projection, transform, frustum));
+fn setup(mut commands: Commands, mut players: Query<&mut Player>) {
+    spawn_cameras(&mut commands);
 
     for mut player in &mut players {
         player.dodge_cooldown.tick(Duration::from_secs_f32(1000.));
@@ -139,6 +180,7 @@ fn setup_purification_one(
 fn setup_purification_two(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    game: Res<Game>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
@@ -193,7 +235,10 @@ fn setup_purification_two(
         },
         Transform::from_xyz(150., -150., LAYER_MOB),
         MobTimeCaster {
-            shoot_cooldown: Timer::from_seconds(0.5, TimerMode::Repeating),
+            shoot_cooldown: Timer::from_seconds(
+                game.difficulty.mob_cooldown(0.5),
+                TimerMode::Repeating,
+            ),
         },
         Enemy,
         Hp(10.),
@@ -205,6 +250,7 @@ fn setup_purification_two(
 fn unleash_the_bees(
     time: Res<Time>,
     game: Res<Game>,
+    mut rng: ResMut<GameRng>,
     mut commands: Commands,
     orb: Query<&Transform, With<MobOrb>>,
     mut onntb: Query<&mut OhNoNotTheBees>,
@@ -229,7 +275,7 @@ fn unleash_the_bees(
     }
     bees.bees_cooldown.reset();
 
-    let dir = rand::thread_rng().gen_range(0..8);
+    let dir = rng.0.gen_range(0..8);
     let theta = (dir as f32) / 4. * PI;
     let vel = Vec3::new(theta.cos() * bee_speed, theta.sin() * bee_speed, 0.);
     let orb_pos = transform_orb.translation;
@@ -245,6 +291,7 @@ fn unleash_the_bees(
             damage: 25.,
             duration: None,
         },
+        DamageSource("bees"),
         PhaseEntity,
     ));
 }
@@ -252,6 +299,7 @@ fn unleash_the_bees(
 fn setup_purification_three(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    game: Res<Game>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
@@ -291,8 +339,8 @@ fn setup_purification_three(
         PhaseEntity,
     ));
 
-    let mut shoot_cooldown = Timer::from_seconds(6., TimerMode::Once);
-    shoot_cooldown.tick(Duration::from_secs_f32(3.));
+    let mut shoot_cooldown = Timer::from_seconds(game.difficulty.mob_cooldown(6.), TimerMode::Once);
+    shoot_cooldown.tick(Duration::from_secs_f32(game.difficulty.mob_cooldown(3.)));
 
     commands.spawn((
         Sprite {
@@ -304,8 +352,10 @@ fn setup_purification_three(
         MobSaltspray {
             shoot_cooldown,
             aoe_desc: AoeDesc {
+                name: "saltspray laser",
                 mesh: laser_mesh,
                 radius: LASER_RADIUS,
+                shape: None,
                 material_base: laser_material,
                 material_detonation,
             },
@@ -345,6 +395,7 @@ fn setup_purification_four(
         },
         Transform::from_xyz(-WIDTH / 2. + 20., -HEIGHT / 2. + 128. + 24., LAYER_UI),
         BossHealthbar,
+        hud_layer(),
         PhaseEntity,
     ));
 
@@ -367,6 +418,7 @@ fn setup_purification_four(
             LAYER_TEXT,
         ),
         BossHealthbarText,
+        hud_layer(),
         PhaseEntity,
     ));
 
@@ -385,6 +437,7 @@ fn setup_purification_four(
             -HEIGHT / 2. + 128. + 8. + 32. + 8.,
             LAYER_TEXT,
         ),
+        hud_layer(),
         PhaseEntity,
     ));
 }
@@ -393,34 +446,49 @@ fn setup_claw_swipes(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
+    boss_path: &BossPath,
     claw_swipe_starts: Vec<f32>,
 ) {
-    let chonk_mesh: Handle<Mesh> = meshes.add(Circle::new(SWIPE_CHONK_RADIUS));
+    // The claw sweeps through the same arc the balls bounce out along
+    let chonk_shape = CollisionShape::Cone {
+        radius: SWIPE_CHONK_RADIUS,
+        half_angle: (SWIPE_END_THETA - SWIPE_START_THETA) / 2.,
+    };
+    let chonk_theta = (SWIPE_START_THETA + SWIPE_END_THETA) / 2.;
+    let chonk_rotation =
+        Quat::from_rotation_arc_2d(Vec2::Y, Vec2::new(-chonk_theta.cos(), chonk_theta.sin()));
+    let chonk_mesh: Handle<Mesh> = meshes.add(chonk_shape.mesh());
     let ball_mesh: Handle<Mesh> = meshes.add(Circle::new(SWIPE_BALL_RADIUS));
     let material_base = materials.add(ColorMaterial::from(AOE_BASE_COLOR));
     let material_detonation = materials.add(ColorMaterial::from(AOE_DETONATION_COLOR));
 
     let aoe_desc_chonk = AoeDesc {
+        name: "claw swipe",
         mesh: chonk_mesh,
-        radius: SWIPE_CHONK_RADIUS,
+        radius: chonk_shape.bounding_radius(),
+        shape: Some(chonk_shape),
         material_base: material_base.clone(),
         material_detonation: material_detonation.clone(),
     };
 
     let aoe_desc = AoeDesc {
+        name: "claw swipe",
         mesh: ball_mesh,
         radius: SWIPE_BALL_RADIUS,
+        shape: None,
         material_base,
         material_detonation,
     };
 
     for claw_swipe_start in claw_swipe_starts {
+        let boss_pose = boss_path.pose_at(claw_swipe_start);
         let chonk_start = Timer::from_seconds(claw_swipe_start, TimerMode::Once);
         let chonk_pos = SWIPE_CENTER;
-        spawn_aoe(
+        spawn_oriented_aoe(
             commands,
             &aoe_desc_chonk,
-            chonk_pos,
+            boss_pose.place(chonk_pos),
+            boss_pose.rotation() * chonk_rotation,
             Aoe {
                 visibility_start: Some(chonk_start),
                 detonation: Timer::from_seconds(SWIPE_DETONATION, TimerMode::Once),
@@ -446,7 +514,7 @@ fn setup_claw_swipes(
                 spawn_aoe(
                     commands,
                     &aoe_desc,
-                    pos,
+                    boss_pose.place(pos),
                     Aoe {
                         visibility_start: Some(timer),
                         detonation: Timer::from_seconds(SWIPE_DETONATION, TimerMode::Once),
@@ -460,6 +528,81 @@ fn setup_claw_swipes(
     }
 }
 
+fn setup_tail_slams(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    boss_path: &BossPath,
+    tail_slams: Vec<TailSlamSpawn>,
+) {
+    let aoe_desc = AoeDesc {
+        name: "tail slam",
+        mesh: meshes.add(Circle::new(TAIL_SLAM_RADIUS)),
+        radius: TAIL_SLAM_RADIUS,
+        shape: None,
+        material_base: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        material_detonation: materials.add(ColorMaterial::from(AOE_DETONATION_COLOR)),
+    };
+
+    for TailSlamSpawn { start, position } in tail_slams {
+        let tail_slam = spawn_aoe(
+            commands,
+            &aoe_desc,
+            boss_path.pose_at(start).place(position.extend(LAYER_AOE)),
+            Aoe {
+                visibility_start: Some(Timer::from_seconds(start, TimerMode::Once)),
+                detonation: Timer::from_seconds(TAIL_SLAM_DETONATION, TimerMode::Once),
+                damage: TAIL_SLAM_DAMAGE,
+                linger: None,
+            },
+            None,
+        );
+        commands
+            .entity(tail_slam)
+            .insert(AoeKnockback(TAIL_SLAM_KNOCKBACK));
+    }
+}
+
+fn setup_bites(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    boss_path: &BossPath,
+    bite_starts: Vec<f32>,
+) {
+    // Snaps straight ahead from the head at the north edge
+    let bite_shape = CollisionShape::Cone {
+        radius: BITE_RADIUS,
+        half_angle: BITE_HALF_ANGLE,
+    };
+    let aoe_desc = AoeDesc {
+        name: "bite",
+        mesh: meshes.add(bite_shape.mesh()),
+        radius: bite_shape.bounding_radius(),
+        shape: Some(bite_shape),
+        material_base: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        material_detonation: materials.add(ColorMaterial::from(AOE_DETONATION_COLOR)),
+    };
+
+    for bite_start in bite_starts {
+        let boss_pose = boss_path.pose_at(bite_start);
+        let bite = spawn_oriented_aoe(
+            commands,
+            &aoe_desc,
+            boss_pose.place(Vec3::new(0., MAP_RADIUS, LAYER_AOE)),
+            boss_pose.rotation() * Quat::from_rotation_z(PI),
+            Aoe {
+                visibility_start: Some(Timer::from_seconds(bite_start, TimerMode::Once)),
+                detonation: Timer::from_seconds(BITE_DETONATION, TimerMode::Once),
+                damage: BITE_DAMAGE,
+                linger: None,
+            },
+            None,
+        );
+        commands.entity(bite).insert(AoeKnockback(BITE_KNOCKBACK));
+    }
+}
+
 fn setup_boss_phase(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
@@ -470,19 +613,39 @@ fn setup_boss_phase(
     green_spawns: Vec<GreenSpawn>,
     puddle_starts: Vec<f32>,
     spread_starts: Vec<f32>,
+    boss_path: BossPath,
 ) {
-    commands.spawn((
-        Mesh2d(meshes.add(Circle::new(BOSS_RADIUS))),
-        MeshMaterial2d(materials.add(ColorMaterial::from(Color::srgba(1.0, 0.0, 0.0, 0.5)))),
-        Transform::from_xyz(0., HEIGHT / 2. + 20., LAYER_MOB),
-        Boss { max_hp: 130. },
-        Enemy,
-        Hp(130.),
-        CollisionRadius(BOSS_RADIUS),
-        PhaseEntity,
-    ));
+    let boss_material = materials.add(ColorMaterial::from(Color::srgba(1.0, 0.0, 0.0, 0.5)));
+    commands
+        .spawn((
+            Mesh2d(meshes.add(Circle::new(BOSS_RADIUS))),
+            MeshMaterial2d(boss_material.clone()),
+            Transform::from_translation(BOSS_HOME.extend(LAYER_MOB)),
+            Boss {
+                max_hp: game.difficulty.boss_hp(130.),
+            },
+            boss_path,
+            Enemy,
+            Hp(game.difficulty.boss_hp(130.)),
+            CollisionRadius(BOSS_RADIUS),
+            PhaseEntity,
+        ))
+        .with_children(|parent| {
+            // Points the way the boss is facing
+            parent.spawn((
+                Mesh2d(meshes.add(Triangle2d::new(
+                    Vec2::new(-BOSS_RADIUS / 4., 0.),
+                    Vec2::new(BOSS_RADIUS / 4., 0.),
+                    Vec2::new(0., -BOSS_RADIUS / 3.),
+                ))),
+                MeshMaterial2d(boss_material),
+                Transform::from_xyz(0., -BOSS_RADIUS, 0.),
+            ));
+        });
 
     setup_greens(commands, meshes, materials, green_spawns.to_vec());
+    setup_timeline_bar(commands, asset_server);
+    setup_cast_bar(commands, asset_server);
 
     commands.spawn((
         Sprite {
@@ -493,6 +656,7 @@ fn setup_boss_phase(
         },
         Transform::from_xyz(-WIDTH / 2. + 20., -HEIGHT / 2. + 128. + 24., LAYER_UI),
         BossHealthbar,
+        hud_layer(),
         PhaseEntity,
     ));
 
@@ -512,6 +676,7 @@ fn setup_boss_phase(
             LAYER_TEXT,
         ),
         BossHealthbarText,
+        hud_layer(),
         PhaseEntity,
     ));
 
@@ -530,6 +695,7 @@ fn setup_boss_phase(
             -HEIGHT / 2. + 128. + 8. + 32. + 8.,
             LAYER_TEXT,
         ),
+        hud_layer(),
         PhaseEntity,
     ));
 
@@ -549,6 +715,7 @@ fn setup_boss_phase(
                 damage: 25.,
                 duration: None,
             },
+            DamageSource("void zone"),
             PhaseEntity,
         ));
     }
@@ -578,24 +745,32 @@ fn setup_boss_phase(
                 .map(|start| Timer::from_seconds(*start, TimerMode::Once))
                 .collect(),
             aoe_desc: AoeDesc {
+                name: "spread",
                 mesh: spread_mesh,
                 material_base: spread_material_base,
                 material_detonation: spread_material_detonation,
                 radius: SPREAD_RADIUS,
+                shape: None,
             },
         })
         .insert(PhaseEntity);
 }
 
-fn setup_jormag(
+fn setup_boss_timeline(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     game: Res<Game>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    timeline: CurrentTimeline,
 ) {
-    let puddle_starts: Vec<f32> = vec![5., 45., 85.];
-    let spread_starts: Vec<f32> = vec![28., 68.];
+    let Some(timeline) = timeline.get() else {
+        error!("no timeline loaded for this phase");
+        return;
+    };
+    let boss_path = BossPath {
+        moves: timeline.boss_moves.clone(),
+    };
 
     setup_boss_phase(
         &mut commands,
@@ -603,22 +778,188 @@ fn setup_jormag(
         &game,
         &mut meshes,
         &mut materials,
-        "Jormag".to_string(),
-        GREEN_SPAWNS_JORMAG.to_vec(),
-        puddle_starts,
-        spread_starts,
+        timeline.boss_name.clone(),
+        timeline.greens.clone(),
+        timeline.puddles.clone(),
+        timeline.spreads.clone(),
+        boss_path.clone(),
     );
 
-    // TODO roving frost beam things properly
+    for mechanic in &timeline.mechanics {
+        if !game.difficulty.has_mechanic(mechanic) {
+            continue;
+        }
+        match mechanic.clone() {
+            Mechanic::Chomps { starts } => {
+                setup_chomps(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &boss_path,
+                    starts,
+                );
+            }
+            Mechanic::MiniChomps { starts } => {
+                setup_minichomps(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &boss_path,
+                    starts,
+                );
+            }
+            Mechanic::DoubleLines { starts } => {
+                setup_double_lines(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &boss_path,
+                    starts,
+                );
+            }
+            Mechanic::MidLines { starts } => {
+                setup_mid_lines(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &boss_path,
+                    starts,
+                );
+            }
+            Mechanic::Waves { waves } => {
+                setup_waves(&mut commands, &asset_server, waves);
+            }
+            Mechanic::Spews {
+                starts,
+                detonation,
+                linger,
+                radius_scale,
+            } => {
+                setup_spews(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    starts,
+                    detonation,
+                    linger,
+                    radius_scale,
+                );
+            }
+            Mechanic::Fears { starts } => {
+                setup_fears(&mut commands, &mut meshes, &mut materials, starts);
+            }
+            Mechanic::Noodles { spawns } => {
+                setup_noodles(
+                    &mut commands,
+                    &asset_server,
+                    &game,
+                    &mut meshes,
+                    &mut materials,
+                    spawns,
+                );
+            }
+            Mechanic::ClawSwipes { starts } => {
+                setup_claw_swipes(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &boss_path,
+                    starts,
+                );
+            }
+            Mechanic::TailSlams { slams } => {
+                setup_tail_slams(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &boss_path,
+                    slams,
+                );
+            }
+            Mechanic::Bites { starts } => {
+                setup_bites(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &boss_path,
+                    starts,
+                );
+            }
+            Mechanic::FrostBeams { beams } => {
+                setup_frost_beams(&mut commands, &mut meshes, &mut materials, beams);
+            }
+            Mechanic::RotatingSoups => {
+                setup_rotating_soups(&mut commands, &mut meshes, &mut materials);
+            }
+            Mechanic::Wyvern { position } => {
+                commands.spawn((
+                    Sprite {
+                        custom_size: Some(Vec2::new(BIGBOY_RADIUS * 2., BIGBOY_RADIUS * 2.)),
+                        image: asset_server.load("wyvern.png"),
+                        ..default()
+                    },
+                    Transform::from_translation(position.extend(LAYER_MOB)),
+                    MobWyvern {
+                        shoot_cooldown: Timer::from_seconds(
+                            game.difficulty.mob_cooldown(1.),
+                            TimerMode::Repeating,
+                        ),
+                        shockwave_cooldown: Timer::from_seconds(
+                            game.difficulty.mob_cooldown(18.),
+                            TimerMode::Repeating,
+                        ),
+                        charge_cooldown: Timer::from_seconds(
+                            game.difficulty.mob_cooldown(11.),
+                            TimerMode::Repeating,
+                        ),
+                    },
+                    Enemy,
+                    Hp(15.),
+                    CollisionRadius(BIGBOY_RADIUS),
+                    PhaseEntity,
+                ));
+            }
+            Mechanic::Goliath { position } => {
+                commands.spawn((
+                    Sprite {
+                        custom_size: Some(Vec2::new(BIGBOY_RADIUS * 2., BIGBOY_RADIUS * 2.)),
+                        image: asset_server.load("goliath.png"),
+                        ..default()
+                    },
+                    Transform::from_translation(position.extend(LAYER_MOB)),
+                    MobGoliath {
+                        shoot_cooldown: Timer::from_seconds(
+                            game.difficulty.mob_cooldown(5.),
+                            TimerMode::Repeating,
+                        ),
+                    },
+                    Enemy,
+                    Hp(10.),
+                    Velocity(Vec3::ZERO),
+                    CollisionRadius(BIGBOY_RADIUS),
+                    PhaseEntity,
+                ));
+            }
+        }
+    }
+}
 
-    let rotating_soup_mesh: Handle<Mesh> = meshes.add(Circle::new(70.));
+fn setup_rotating_soups(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+) {
+    let rotating_soup_mesh: Handle<Mesh> = meshes.add(Circle::new(ROTATING_SOUP_RADIUS));
     let rotating_soup_material =
         materials.add(ColorMaterial::from(Color::srgba(0.0, 0.0, 0.0, 0.3)));
 
-    for i in 0..4 {
-        let radius = 0.;
-        let theta = i as f32 * PI / 2.;
-        let dtheta = 0.5;
+    for i in 1..=5 {
+        let radius = (i as f32) / 5. * (HEIGHT / 2. - 20.);
+        let theta = i as f32 * 6. * PI / 5.;
+        let mut dtheta = (7. - (i as f32)) / 5. * ROTATING_SOUP_DTHETA;
+        if i % 2 == 0 {
+            dtheta = -dtheta;
+        }
 
         commands.spawn((
             Mesh2d(rotating_soup_mesh.clone()),
@@ -629,68 +970,43 @@ fn setup_jormag(
                 theta,
                 dtheta,
             },
-            CollisionRadius(70.),
+            CollisionRadius(ROTATING_SOUP_RADIUS),
             Soup {
                 damage: 5.,
                 duration: None,
             },
+            DamageSource("rotating soup"),
             PhaseEntity,
         ));
     }
 }
 
-fn setup_primordus(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    game: Res<Game>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+fn setup_chomps(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    boss_path: &BossPath,
+    chomp_starts: Vec<f32>,
 ) {
-    let puddle_starts: Vec<f32> = vec![17., 71.];
-    let spread_starts: Vec<f32> = vec![13., 67.];
-    let chomp_starts: Vec<f32> = vec![13., 67.];
-    let minichomp_starts: Vec<f32> = vec![26., 39., 52., 80., 93., 106.];
-
-    setup_boss_phase(
-        &mut commands,
-        &asset_server,
-        &game,
-        &mut meshes,
-        &mut materials,
-        "Primordus".to_string(),
-        GREEN_SPAWNS_PRIMORDUS.to_vec(),
-        puddle_starts,
-        spread_starts,
-    );
-
     let chomp_y = HEIGHT / 2. - BOSS_RADIUS;
     let chomp_radius = CHOMP_TARGET_Y - BOSS_RADIUS;
-    let minichomp_radius = MINICHOMP_TARGET_Y - BOSS_RADIUS;
-
-    let chomp_mesh: Handle<Mesh> = meshes.add(Circle::new(chomp_radius));
-    let minichomp_mesh: Handle<Mesh> = meshes.add(Circle::new(minichomp_radius));
-    let material_base = materials.add(ColorMaterial::from(AOE_BASE_COLOR));
-    let material_detonation = materials.add(ColorMaterial::from(AOE_DETONATION_COLOR));
 
     let aoe_desc_chomp = AoeDesc {
-        mesh: chomp_mesh,
+        name: "chomp",
+        mesh: meshes.add(Circle::new(chomp_radius)),
         radius: chomp_radius,
-        material_base: material_base.clone(),
-        material_detonation: material_detonation.clone(),
-    };
-
-    let aoe_desc_minichomp = AoeDesc {
-        mesh: minichomp_mesh,
-        radius: minichomp_radius,
-        material_base: material_base.clone(),
-        material_detonation: material_detonation.clone(),
+        shape: None,
+        material_base: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        material_detonation: materials.add(ColorMaterial::from(AOE_DETONATION_COLOR)),
     };
 
     for chomp_start in chomp_starts {
         spawn_aoe(
-            &mut commands,
+            commands,
             &aoe_desc_chomp,
-            Vec3::new(0., chomp_y, LAYER_AOE),
+            boss_path
+                .pose_at(chomp_start)
+                .place(Vec3::new(0., chomp_y, LAYER_AOE)),
             Aoe {
                 visibility_start: Some(Timer::from_seconds(chomp_start, TimerMode::Once)),
                 detonation: Timer::from_seconds(7., TimerMode::Once),
@@ -700,12 +1016,34 @@ fn setup_primordus(
             None,
         );
     }
+}
+
+fn setup_minichomps(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    boss_path: &BossPath,
+    minichomp_starts: Vec<f32>,
+) {
+    let chomp_y = HEIGHT / 2. - BOSS_RADIUS;
+    let minichomp_radius = MINICHOMP_TARGET_Y - BOSS_RADIUS;
+
+    let aoe_desc_minichomp = AoeDesc {
+        name: "mini chomp",
+        mesh: meshes.add(Circle::new(minichomp_radius)),
+        radius: minichomp_radius,
+        shape: None,
+        material_base: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        material_detonation: materials.add(ColorMaterial::from(AOE_DETONATION_COLOR)),
+    };
 
     for minichomp_start in minichomp_starts {
         spawn_aoe(
-            &mut commands,
+            commands,
             &aoe_desc_minichomp,
-            Vec3::new(0., chomp_y, LAYER_AOE),
+            boss_path
+                .pose_at(minichomp_start)
+                .place(Vec3::new(0., chomp_y, LAYER_AOE)),
             Aoe {
                 visibility_start: Some(Timer::from_seconds(minichomp_start, TimerMode::Once)),
                 detonation: Timer::from_seconds(3., TimerMode::Once),
@@ -717,105 +1055,110 @@ fn setup_primordus(
     }
 }
 
-fn setup_kralkatorrik(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    game: Res<Game>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let puddle_starts: Vec<f32> = vec![18., 43., 68., 93.];
-    let spread_starts: Vec<f32> = vec![];
-    let line_delay: f32 = 2.;
-    let line_duration: f32 = 5.;
-    let double_line_starts: Vec<f32> = vec![5., 30., 54., 78., 102.];
-    let mid_line_starts: Vec<f32> = vec![18., 42., 66., 90.];
-
-    setup_boss_phase(
-        &mut commands,
-        &asset_server,
-        &game,
-        &mut meshes,
-        &mut materials,
-        "Kralkatorrik".to_string(),
-        vec![],
-        puddle_starts,
-        spread_starts,
-    );
+fn line_aoe_desc(
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+) -> AoeDesc {
+    let shape = CollisionShape::Rect {
+        half_size: Vec2::new(LINE_RADIUS, LINE_SEGMENT_LENGTH / 2.),
+    };
+    AoeDesc {
+        name: "line",
+        mesh: meshes.add(shape.mesh()),
+        radius: shape.bounding_radius(),
+        shape: Some(shape),
+        material_base: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        material_detonation: materials.add(ColorMaterial::from(Color::srgb(0., 0., 0.))),
+    }
+}
 
-    let line_radius = BOSS_RADIUS * 0.9;
-    let line_x = BOSS_RADIUS * 0.3 + line_radius;
-    let line_spacing = line_radius;
-    let line_circles = (GAME_WIDTH / line_spacing) as i32;
+fn line_segment_y(i: i32) -> f32 {
+    (i as f32 + 0.5) * LINE_SEGMENT_LENGTH - GAME_WIDTH / 2.
+}
 
-    let mesh: Handle<Mesh> = meshes.add(Circle::new(line_radius));
-    let material_base = materials.add(ColorMaterial::from(AOE_BASE_COLOR));
-    let material_detonation = materials.add(ColorMaterial::from(Color::srgb(0., 0., 0.)));
+fn setup_double_lines(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    boss_path: &BossPath,
+    double_line_starts: Vec<f32>,
+) {
+    let line_x = BOSS_RADIUS * 0.3 + LINE_RADIUS;
 
-    let aoe_desc = AoeDesc {
-        mesh,
-        radius: line_radius,
-        material_base,
-        material_detonation,
-    };
+    let aoe_desc = line_aoe_desc(meshes, materials);
 
     for line_start in double_line_starts {
-        for i in 0..line_circles {
-            let delay = 0.5 - i as f32 / (2. * line_circles as f32);
-            let mut pos = Vec3::new(line_x, i as f32 * line_spacing - GAME_WIDTH / 2., LAYER_AOE);
+        let boss_pose = boss_path.pose_at(line_start);
+        for i in 0..LINE_SEGMENTS {
+            let delay = 0.5 - i as f32 / (2. * LINE_SEGMENTS as f32);
+            let mut pos = Vec3::new(line_x, line_segment_y(i), LAYER_AOE);
 
-            spawn_aoe(
-                &mut commands,
+            spawn_oriented_aoe(
+                commands,
                 &aoe_desc,
-                pos,
+                boss_pose.place(pos),
+                boss_pose.rotation(),
                 Aoe {
                     visibility_start: Some(Timer::from_seconds(
                         line_start + delay,
                         TimerMode::Once,
                     )),
-                    detonation: Timer::from_seconds(line_delay, TimerMode::Once),
+                    detonation: Timer::from_seconds(LINE_DELAY, TimerMode::Once),
                     damage: SPEW_DAMAGE,
-                    linger: Some(Timer::from_seconds(line_duration, TimerMode::Once)),
+                    linger: Some(Timer::from_seconds(LINE_DURATION, TimerMode::Once)),
                 },
                 None,
             );
 
             pos.x *= -1.;
-            spawn_aoe(
-                &mut commands,
+            spawn_oriented_aoe(
+                commands,
                 &aoe_desc,
-                pos,
+                boss_pose.place(pos),
+                boss_pose.rotation(),
                 Aoe {
                     visibility_start: Some(Timer::from_seconds(
                         line_start + delay,
                         TimerMode::Once,
                     )),
-                    detonation: Timer::from_seconds(line_delay, TimerMode::Once),
+                    detonation: Timer::from_seconds(LINE_DELAY, TimerMode::Once),
                     damage: SPEW_DAMAGE,
-                    linger: Some(Timer::from_seconds(line_duration, TimerMode::Once)),
+                    linger: Some(Timer::from_seconds(LINE_DURATION, TimerMode::Once)),
                 },
                 None,
             );
         }
     }
+}
+
+fn setup_mid_lines(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    boss_path: &BossPath,
+    mid_line_starts: Vec<f32>,
+) {
+    let aoe_desc = line_aoe_desc(meshes, materials);
 
     for line_start in mid_line_starts {
-        for i in 0..line_circles {
-            let delay = i as f32 / (2. * line_circles as f32);
-            let pos = Vec3::new(0., i as f32 * line_spacing - GAME_WIDTH / 2., LAYER_AOE);
+        let boss_pose = boss_path.pose_at(line_start);
+        for i in 0..LINE_SEGMENTS {
+            let delay = i as f32 / (2. * LINE_SEGMENTS as f32);
+            let pos = Vec3::new(0., line_segment_y(i), LAYER_AOE);
 
-            spawn_aoe(
-                &mut commands,
+            spawn_oriented_aoe(
+                commands,
                 &aoe_desc,
-                pos,
+                boss_pose.place(pos),
+                boss_pose.rotation(),
                 Aoe {
                     visibility_start: Some(Timer::from_seconds(
                         line_start + delay,
                         TimerMode::Once,
                     )),
-                    detonation: Timer::from_seconds(line_delay, TimerMode::Once),
+                    detonation: Timer::from_seconds(LINE_DELAY, TimerMode::Once),
                     damage: SPREAD_DAMAGE,
-                    linger: Some(Timer::from_seconds(line_duration, TimerMode::Once)),
+                    linger: Some(Timer::from_seconds(LINE_DURATION, TimerMode::Once)),
                 },
                 None,
             );
@@ -823,167 +1166,108 @@ fn setup_kralkatorrik(
     }
 }
 
-fn setup_mordremoth(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    game: Res<Game>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let puddle_starts: Vec<f32> = vec![8., 30., 53., 82.];
-    let spread_starts: Vec<f32> = vec![18., 40., 63., 91.];
-    let boop_starts: Vec<f32> = vec![22., 44., 67., 95.];
-    let boop_delays: Vec<f32> = vec![0., 2., 4.]; // 21.5, 24, 26 hmm
-    let spew_starts: Vec<f32> = vec![13., 35., 58., 87.];
-
-    setup_boss_phase(
-        &mut commands,
-        &asset_server,
-        &game,
-        &mut meshes,
-        &mut materials,
-        "Mordremoth".to_string(),
-        vec![],
-        puddle_starts,
-        spread_starts,
-    );
-
-    let wave_texture = asset_server.load("wave.png");
-
-    for boop_start in &boop_starts {
-        for boop_delay in &boop_delays {
-            commands.spawn((
-                Sprite {
-                    image: wave_texture.clone(),
-                    custom_size: Some(Vec2::new(WAVE_MAX_RADIUS * 2., WAVE_MAX_RADIUS * 2.)),
-                    ..default()
-                },
-                Transform::from_xyz(0., 0., LAYER_WAVE).with_scale(Vec3::ZERO),
-                Wave {
-                    visibility_start: Timer::from_seconds(boop_start + boop_delay, TimerMode::Once),
-                    ..default()
-                },
-                PhaseEntity,
-            ));
-        }
-    }
-
-    let spew_mesh: Handle<Mesh> = meshes.add(Circle::new(SPEW_RADIUS));
-    let material_base = materials.add(ColorMaterial::from(AOE_BASE_COLOR));
-    let material_detonation = materials.add(ColorMaterial::from(AOE_DETONATION_COLOR));
-
-    let aoe_desc_spew = AoeDesc {
-        mesh: spew_mesh,
-        radius: SPEW_RADIUS,
-        material_base: material_base.clone(),
-        material_detonation: material_detonation.clone(),
+fn setup_waves(commands: &mut Commands, asset_server: &Res<AssetServer>, waves: Vec<WaveSpawn>) {
+    let wave_sprite = Sprite {
+        custom_size: Some(Vec2::new(WAVE_MAX_RADIUS * 2., WAVE_MAX_RADIUS * 2.)),
+        image: asset_server.load("wave.png"),
+        ..default()
     };
 
-    for spew_start in spew_starts {
-        spawn_spew_aoe(&mut commands, spew_start, 1.5, &aoe_desc_spew, None);
+    for wave in waves {
+        commands.spawn((
+            wave_sprite.clone(),
+            Transform::from_translation(wave.position.extend(LAYER_WAVE)).with_scale(Vec3::ZERO),
+            Wave {
+                visibility_start: Timer::from_seconds(wave.start, TimerMode::Once),
+                ..default()
+            },
+            PhaseEntity,
+        ));
     }
 }
 
-fn setup_zhaitan(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    game: Res<Game>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+fn setup_spews(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    spew_starts: Vec<f32>,
+    detonation: f32,
+    linger: Option<f32>,
+    radius_scale: f32,
 ) {
-    // Timed relative to the first green (-5 seconds)
-    let puddle_starts: Vec<f32> = vec![9., 42., 74.].iter().map(|x| x + 5.).collect();
-    let spread_starts: Vec<f32> = vec![18., 51., 83.].iter().map(|x| x + 5.).collect();
-    let fear_starts: Vec<f32> = vec![14., 47., 79.].iter().map(|x| x + 5.).collect();
-    let spew_starts: Vec<f32> = vec![3., 68.];
-
-    setup_boss_phase(
-        &mut commands,
-        &asset_server,
-        &game,
-        &mut meshes,
-        &mut materials,
-        "Zhaitan".to_string(),
-        GREEN_SPAWNS_ZHAITAN.to_vec(),
-        puddle_starts,
-        spread_starts,
-    );
-
-    let spew_radius_nerfed = SPEW_RADIUS * 0.9;
-    let spew_mesh: Handle<Mesh> = meshes.add(Circle::new(spew_radius_nerfed));
-    let fear_mesh: Handle<Mesh> = meshes.add(Circle::new(WIDTH / 2.));
-    let noodle_aoe_mesh: Handle<Mesh> = meshes.add(Circle::new(NOODLE_SLAM_RADIUS));
-    let material_base = materials.add(ColorMaterial::from(AOE_BASE_COLOR));
-    let material_detonation = materials.add(ColorMaterial::from(AOE_DETONATION_COLOR));
+    let spew_radius = SPEW_RADIUS * radius_scale;
 
     let aoe_desc_spew = AoeDesc {
-        mesh: spew_mesh,
-        radius: spew_radius_nerfed,
-        material_base: material_base.clone(),
-        material_detonation: material_detonation.clone(),
+        name: "spew",
+        mesh: meshes.add(Circle::new(spew_radius)),
+        radius: spew_radius,
+        shape: None,
+        material_base: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        material_detonation: materials.add(ColorMaterial::from(AOE_DETONATION_COLOR)),
     };
 
     for spew_start in spew_starts {
         spawn_spew_aoe(
-            &mut commands,
+            commands,
             spew_start,
-            3.,
+            detonation,
             &aoe_desc_spew,
-            Some(Timer::from_seconds(10., TimerMode::Once)),
+            linger.map(|linger| Timer::from_seconds(linger, TimerMode::Once)),
         );
     }
+}
 
+fn setup_fears(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    fear_starts: Vec<f32>,
+) {
     let aoe_desc_fear = AoeDesc {
-        mesh: fear_mesh,
+        name: "fear",
+        mesh: meshes.add(Circle::new(WIDTH / 2.)),
         radius: WIDTH / 2.,
-        material_base: material_base.clone(),
-        material_detonation: material_detonation.clone(),
+        shape: None,
+        material_base: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        material_detonation: materials.add(ColorMaterial::from(AOE_DETONATION_COLOR)),
     };
 
     for fear_start in fear_starts {
-        spawn_aoe(
-            &mut commands,
+        let fear = spawn_aoe(
+            commands,
             &aoe_desc_fear,
             Vec3::new(0., 0., LAYER_AOE),
             Aoe {
                 visibility_start: Some(Timer::from_seconds(fear_start, TimerMode::Once)),
                 detonation: Timer::from_seconds(2.5, TimerMode::Once),
-                damage: 30.,
+                damage: 0.,
                 linger: None,
             },
             None,
         );
+        commands.entity(fear).insert(Fear);
     }
+}
 
+fn setup_noodles(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    game: &Res<Game>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    noodle_spawns: Vec<NoodleSpawn>,
+) {
     let aoe_desc_noodle = AoeDesc {
-        mesh: noodle_aoe_mesh,
+        name: "noodle slam",
+        mesh: meshes.add(Circle::new(NOODLE_SLAM_RADIUS)),
         radius: NOODLE_SLAM_RADIUS,
-        material_base: material_base.clone(),
-        material_detonation: material_detonation.clone(),
+        shape: None,
+        material_base: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        material_detonation: materials.add(ColorMaterial::from(AOE_DETONATION_COLOR)),
     };
 
-    // There is a third spawn but it doesn't really do much all things considered
-    let noodle_spawns = vec![
-        (
-            5.,
-            vec![
-                Transform::from_xyz(-36., 224., LAYER_MOB),
-                Transform::from_xyz(375., -80., LAYER_MOB),
-                Transform::from_xyz(-120., -255., LAYER_MOB),
-            ],
-        ),
-        (
-            37.,
-            vec![
-                Transform::from_xyz(-36., 400., LAYER_MOB),
-                Transform::from_xyz(-142., -142., LAYER_MOB),
-                Transform::from_xyz(275., -104., LAYER_MOB),
-            ],
-        ),
-    ];
-
-    for (visibility_start, noodle_positions) in noodle_spawns {
-        for noodle_pos in noodle_positions {
+    for noodle_spawn in noodle_spawns {
+        for noodle_pos in noodle_spawn.positions {
             commands.spawn((
                 Sprite {
                     custom_size: Some(Vec2::new(NOODLE_RADIUS * 2., NOODLE_RADIUS * 2.)),
@@ -991,10 +1275,13 @@ fn setup_zhaitan(
                     ..default()
                 },
                 Visibility::Hidden,
-                noodle_pos,
+                Transform::from_translation(noodle_pos.extend(LAYER_MOB)),
                 MobNoodle {
-                    visibility_start: Timer::from_seconds(visibility_start, TimerMode::Once),
-                    slam_cooldown: Timer::from_seconds(5., TimerMode::Repeating),
+                    visibility_start: Timer::from_seconds(noodle_spawn.start, TimerMode::Once),
+                    slam_cooldown: Timer::from_seconds(
+                        game.difficulty.mob_cooldown(5.),
+                        TimerMode::Repeating,
+                    ),
                     aoe_desc: aoe_desc_noodle.clone(),
                 },
                 Enemy,
@@ -1006,283 +1293,6 @@ fn setup_zhaitan(
     }
 }
 
-fn setup_soowonone(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    game: Res<Game>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let puddle_starts: Vec<f32> = vec![4., 25., 49., 70., 94.];
-    let spread_starts: Vec<f32> = vec![12., 58., 103.];
-
-    setup_boss_phase(
-        &mut commands,
-        &asset_server,
-        &game,
-        &mut meshes,
-        &mut materials,
-        "Soo-Won 1".to_string(),
-        GREEN_SPAWNS_SOOWONONE.to_vec(),
-        puddle_starts,
-        spread_starts,
-    );
-
-    let wave_texture = asset_server.load("wave.png");
-
-    let wave_sprite = Sprite {
-        custom_size: Some(Vec2::new(WAVE_MAX_RADIUS * 2., WAVE_MAX_RADIUS * 2.)),
-        image: wave_texture.clone(),
-        ..default()
-    };
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(-140., 300., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(7., TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(0., 0., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(32., TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(-140., 300., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(52., TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(0., 0., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(77., TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(-140., 300., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(97., TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    let rotating_soup_mesh: Handle<Mesh> = meshes.add(Circle::new(ROTATING_SOUP_RADIUS));
-    let rotating_soup_material =
-        materials.add(ColorMaterial::from(Color::srgba(0.0, 0.0, 0.0, 0.3)));
-
-    for i in 1..=5 {
-        let radius = (i as f32) / 5. * (HEIGHT / 2. - 20.);
-        let theta = i as f32 * 6. * PI / 5.;
-        let mut dtheta = (7. - (i as f32)) / 5. * ROTATING_SOUP_DTHETA;
-        if i % 2 == 0 {
-            dtheta = -dtheta;
-        }
-
-        commands.spawn((
-            Mesh2d(rotating_soup_mesh.clone()),
-            MeshMaterial2d(rotating_soup_material.clone()),
-            Transform::from_xyz(0., radius, LAYER_ROTATING_SOUP),
-            RotatingSoup {
-                radius,
-                theta,
-                dtheta,
-            },
-            CollisionRadius(ROTATING_SOUP_RADIUS),
-            Soup {
-                damage: 5.,
-                duration: None,
-            },
-            PhaseEntity,
-        ));
-    }
-
-    setup_claw_swipes(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
-        vec![15., 60., 105.],
-    );
-}
-
-fn setup_soowontwo(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    game: Res<Game>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let puddle_starts: Vec<f32> = vec![10.7, 28.3, 52., 73.9, 103.];
-    let spread_starts: Vec<f32> = vec![20.2, 65.9, 103. + 9.5];
-
-    setup_boss_phase(
-        &mut commands,
-        &asset_server,
-        &game,
-        &mut meshes,
-        &mut materials,
-        "Soo-Won 2".to_string(),
-        GREEN_SPAWNS_SOOWONTWO.to_vec(),
-        puddle_starts,
-        spread_starts,
-    );
-
-    let wave_texture = asset_server.load("wave.png");
-    let wave_sprite = Sprite {
-        custom_size: Some(Vec2::new(WAVE_MAX_RADIUS * 2., WAVE_MAX_RADIUS * 2.)),
-        image: wave_texture.clone(),
-        ..default()
-    };
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(-140., 300., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(13.8, TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(0., 0., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(34.9, TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(-140., 300., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(55.4, TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(0., 0., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(80.6, TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        wave_sprite.clone(),
-        Transform::from_xyz(-140., 300., LAYER_WAVE).with_scale(Vec3::ZERO),
-        Wave {
-            visibility_start: Timer::from_seconds(106., TimerMode::Once),
-            ..default()
-        },
-        PhaseEntity,
-    ));
-
-    let rotating_soup_mesh: Handle<Mesh> = meshes.add(Circle::new(ROTATING_SOUP_RADIUS));
-    let rotating_soup_material =
-        materials.add(ColorMaterial::from(Color::srgba(0.0, 0.0, 0.0, 0.3)));
-
-    for i in 1..=5 {
-        let radius = (i as f32) / 5. * (HEIGHT / 2. - 20.);
-        let theta = i as f32 * 6. * PI / 5.;
-        let mut dtheta = (7. - (i as f32)) / 5. * ROTATING_SOUP_DTHETA;
-        if i % 2 == 0 {
-            dtheta = -dtheta;
-        }
-
-        commands.spawn((
-            Mesh2d(rotating_soup_mesh.clone()),
-            MeshMaterial2d(rotating_soup_material.clone()),
-            Transform::from_xyz(0., radius, LAYER_ROTATING_SOUP),
-            RotatingSoup {
-                radius,
-                theta,
-                dtheta,
-            },
-            CollisionRadius(ROTATING_SOUP_RADIUS),
-            Soup {
-                damage: 5.,
-                duration: None,
-            },
-            PhaseEntity,
-        ));
-    }
-
-    setup_claw_swipes(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
-        vec![22.3, 68., 103. + 9.5 + 2.1],
-    );
-
-    commands.spawn((
-        Sprite {
-            custom_size: Some(Vec2::new(BIGBOY_RADIUS * 2., BIGBOY_RADIUS * 2.)),
-            image: asset_server.load("wyvern.png"),
-            ..default()
-        },
-        Transform::from_xyz(400., 0., LAYER_MOB),
-        MobWyvern {
-            shoot_cooldown: Timer::from_seconds(1., TimerMode::Repeating),
-            shockwave_cooldown: Timer::from_seconds(18., TimerMode::Repeating),
-            charge_cooldown: Timer::from_seconds(11., TimerMode::Repeating),
-        },
-        Enemy,
-        Hp(15.),
-        CollisionRadius(BIGBOY_RADIUS),
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        Sprite {
-            custom_size: Some(Vec2::new(BIGBOY_RADIUS * 2., BIGBOY_RADIUS * 2.)),
-            image: asset_server.load("goliath.png"),
-            ..default()
-        },
-        Transform::from_xyz(300., 0., LAYER_MOB),
-        MobGoliath {
-            shoot_cooldown: Timer::from_seconds(5., TimerMode::Repeating),
-        },
-        Enemy,
-        Hp(10.),
-        Velocity(Vec3::ZERO),
-        CollisionRadius(BIGBOY_RADIUS),
-        PhaseEntity,
-    ));
-}
-
-fn jormag_soup_beam_system(time: Res<Time>, mut soups: Query<&mut RotatingSoup>) {
-    for mut soup in &mut soups {
-        let radius = (WIDTH / 2. - 70.) * ((time.elapsed_secs() / 8.).cos() + 1.) / 2. + 35.;
-        soup.radius = radius;
-    }
-}
-
 fn run_if_phase_update(
     menu_state: Res<State<MenuState>>,
     game_state: Res<State<GameState>>,
@@ -1354,82 +1364,41 @@ fn run_if_purification_phase_update(
     }
 }
 
-fn main() {
-    let game = Game {
-        time_elapsed: Stopwatch::new(),
-        player_damage_taken: 0.,
-        continuous: false,
-        orb_target: -1,
-        echo_enabled: false,
-        hints_enabled: true,
-        hint: None,
-        greens_enabled: true,
-        puddles_enabled: true,
-        unlimited_range_enabled: true,
-        ai_enabled: true,
-        ai_bars_enabled: true,
-        player_role: Some(AiRole::Virt1),
-        audio_enabled: true,
-    };
-
-    let binding = App::new();
-    let mut app = binding;
-    app.add_plugins(DefaultPlugins.set(WindowPlugin {
-        primary_window: Some(Window {
-            resolution: WindowResolution::new(WIDTH, HEIGHT).with_scale_factor_override(1.),
-            ..default()
-        }),
-        ..default()
-    }))
-    .init_state::<GameState>()
-    .init_state::<MenuState>()
-    .add_event::<DamageFlashEvent>()
-    .add_event::<RestartEvent>()
-    .insert_resource(game)
-    .insert_resource(ClearColor(Color::srgb(0.3, 0.3, 0.3)))
-    .insert_resource(AssetsLoading(vec![]))
-    .add_systems(Startup, setup)
-    .add_systems(OnEnter(MenuState::Loading), setup_loading_system)
-    .add_systems(
-        Update,
-        (update_loading_system).run_if(in_state(MenuState::Loading)),
-    )
-    .add_systems(OnExit(MenuState::Loading), cleanup_menu_system)
-    .add_systems(OnEnter(MenuState::StartMenu), setup_menu_system)
-    .add_systems(
-        Update,
-        (update_menu_system, update_menu_onoff_system).run_if(in_state(MenuState::StartMenu)),
-    )
-    .add_systems(OnExit(MenuState::StartMenu), cleanup_menu_system)
-    .add_systems(OnEnter(MenuState::Paused), setup_pause_menu_system)
-    .add_systems(
-        Update,
-        update_menu_system.run_if(in_state(MenuState::Paused)),
-    )
-    .add_systems(OnExit(MenuState::Paused), cleanup_menu_system)
-    .add_systems(OnEnter(MenuState::PausedShowHint), setup_show_hint_system)
-    .add_systems(
-        Update,
-        update_menu_system.run_if(in_state(MenuState::PausedShowHint)),
-    )
-    .add_systems(OnExit(MenuState::PausedShowHint), cleanup_menu_system)
-    .add_systems(OnEnter(MenuState::Success), setup_success_system)
-    .add_systems(
-        Update,
-        update_menu_system.run_if(in_state(MenuState::Success)),
-    )
-    .add_systems(OnExit(MenuState::Success), cleanup_menu_system)
-    .add_systems(OnEnter(MenuState::Failure), setup_failure_system)
-    .add_systems(
+fn add_phase_systems(app: &mut App) {
+    app.init_state::<GameState>()
+        .init_state::<MenuState>()
+        .add_event::<DamageFlashEvent>()
+        .add_event::<RestartEvent>()
+        .add_event::<PlayerDeathEvent>()
+        .init_resource::<InputBindings>()
+        .init_asset::<PhaseTimeline>()
+        .init_asset_loader::<PhaseTimelineLoader>();
+
+    add_replay_systems(app);
+    add_update_phase_set(app);
+    app.add_systems(
         Update,
-        update_menu_system.run_if(in_state(MenuState::Failure)),
-    )
-    .add_systems(OnExit(MenuState::Failure), cleanup_menu_system)
-    .add_systems(Update, restart_event_system);
+        game_speed_system
+            .before(PhaseSet::UpdatePhase)
+            // Only reads the speed, which fast forwarding leaves alone
+            .ambiguous_with(fast_forward_phase_system),
+    );
+    add_update_purification_phase_set(app);
+    add_update_boss_phase_set(app);
 
-    add_update_phase_set(&mut app);
-    add_update_purification_phase_set(&mut app);
-    add_update_boss_phase_set(&mut app);
+    app.add_systems(
+        Update,
+        (
+            fast_forward_aoes_system,
+            fast_forward_mechanics_system,
+            fast_forward_phase_system,
+        )
+            .chain()
+            .run_if(resource_exists::<FastForward>)
+            .before(PhaseSet::UpdatePhase)
+            .before(PhaseSet::UpdatePurificationPhase)
+            .before(PhaseSet::UpdateBossPhase),
+    );
 
     app.add_systems(OnEnter(GameState::PurificationOne), setup_phase)
         .add_systems(OnEnter(GameState::Jormag), setup_phase)
@@ -1461,6 +1430,18 @@ fn main() {
 
     app.configure_sets(Update, (PhaseSet::UpdatePhase).run_if(run_if_phase_update));
 
+    // The sets share players and the game state, so they always run in the
+    // same order for replays to line up
+    app.configure_sets(
+        Update,
+        (
+            PhaseSet::UpdatePhase,
+            PhaseSet::UpdatePurificationPhase,
+            PhaseSet::UpdateBossPhase,
+        )
+            .chain(),
+    );
+
     app.configure_sets(
         Update,
         (PhaseSet::UpdatePurificationPhase).run_if(run_if_purification_phase_update),
@@ -1475,20 +1456,17 @@ fn main() {
         OnEnter(GameState::PurificationOne),
         setup_purification_one.after(setup_purification),
     )
-    .add_systems(OnEnter(GameState::Jormag), setup_jormag.after(setup_phase))
     .add_systems(
-        Update,
-        jormag_soup_beam_system
-            .run_if(in_state(GameState::Jormag))
-            .run_if(in_state(MenuState::Unpaused)),
+        OnEnter(GameState::Jormag),
+        setup_boss_timeline.after(setup_phase),
     )
     .add_systems(
         OnEnter(GameState::Primordus),
-        setup_primordus.after(setup_phase),
+        setup_boss_timeline.after(setup_phase),
     )
     .add_systems(
         OnEnter(GameState::Kralkatorrik),
-        setup_kralkatorrik.after(setup_phase),
+        setup_boss_timeline.after(setup_phase),
     )
     .add_systems(
         OnEnter(GameState::PurificationTwo),
@@ -1503,21 +1481,24 @@ fn main() {
             unleash_the_bees
                 .run_if(in_state(GameState::PurificationTwo))
                 .run_if(in_state(MenuState::Unpaused)),
-        ),
+        )
+            .chain()
+            .after(PhaseSet::UpdateBossPhase),
     )
     .add_systems(
         OnEnter(GameState::Mordremoth),
-        setup_mordremoth.after(setup_phase),
+        setup_boss_timeline.after(setup_phase),
     )
     .add_systems(
         OnEnter(GameState::Zhaitan),
-        setup_zhaitan.after(setup_phase),
+        setup_boss_timeline.after(setup_phase),
     )
     .add_systems(
         Update,
         noodle_system
             .run_if(in_state(GameState::Zhaitan))
-            .run_if(in_state(MenuState::Unpaused)),
+            .run_if(in_state(MenuState::Unpaused))
+            .after(unleash_the_bees),
     )
     .add_systems(
         OnEnter(GameState::PurificationThree),
@@ -1535,11 +1516,13 @@ fn main() {
             aoes_detonation_system
                 .run_if(in_state(GameState::PurificationThree))
                 .run_if(in_state(MenuState::Unpaused)),
-        ),
+        )
+            .chain()
+            .after(noodle_system),
     )
     .add_systems(
         OnEnter(GameState::SooWonOne),
-        setup_soowonone.after(setup_phase),
+        setup_boss_timeline.after(setup_phase),
     )
     .add_systems(
         OnEnter(GameState::PurificationFour),
@@ -1563,11 +1546,14 @@ fn main() {
             player_ai_purification_phase_system
                 .run_if(in_state(GameState::PurificationFour))
                 .run_if(in_state(MenuState::Unpaused)),
-        ),
+        )
+            .chain()
+            .after(noodle_system)
+            .before(saltspray_system),
     )
     .add_systems(
         OnEnter(GameState::SooWonTwo),
-        setup_soowontwo.after(setup_phase),
+        setup_boss_timeline.after(setup_phase),
     )
     .add_systems(
         Update,
@@ -1578,7 +1564,138 @@ fn main() {
             wyvern_system
                 .run_if(in_state(GameState::SooWonTwo))
                 .run_if(in_state(MenuState::Unpaused)),
-        ),
+        )
+            .chain()
+            .after(PhaseSet::UpdateBossPhase)
+            .before(unleash_the_bees),
+    );
+}
+
+fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    if sim::run_from_args() {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if net::run_client_from_args() {
+        return;
+    }
+
+    let mut game = Game::default();
+    let mut bindings = InputBindings::default();
+    if let Some(settings) = load_settings() {
+        settings.apply(&mut game, &mut bindings);
+    }
+    let global_volume = if game.audio_enabled { 1.0 } else { 0.0 };
+
+    let binding = App::new();
+    let mut app = binding;
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            resolution: WindowResolution::new(WIDTH, HEIGHT).with_scale_factor_override(1.),
+            ..default()
+        }),
+        ..default()
+    }))
+    .insert_resource(game)
+    .insert_resource(bindings)
+    .insert_resource(GlobalVolume::new(global_volume))
+    .insert_resource(ClearColor(Color::srgb(0.3, 0.3, 0.3)))
+    .insert_resource(AssetsLoading(vec![]))
+    .add_systems(Startup, setup)
+    .add_systems(OnEnter(MenuState::Loading), setup_loading_system)
+    .add_systems(
+        Update,
+        (update_loading_system).run_if(in_state(MenuState::Loading)),
     )
-    .run();
+    .add_systems(OnExit(MenuState::Loading), cleanup_menu_system)
+    .add_systems(OnEnter(MenuState::StartMenu), setup_menu_system)
+    .add_systems(
+        Update,
+        (update_menu_system, update_menu_onoff_system).run_if(in_state(MenuState::StartMenu)),
+    )
+    .add_systems(OnExit(MenuState::StartMenu), cleanup_menu_system)
+    .add_systems(OnEnter(MenuState::Controls), setup_controls_menu_system)
+    .add_systems(
+        Update,
+        (
+            update_menu_system,
+            update_controls_menu_system,
+            rebind_capture_system,
+            update_rebind_labels_system,
+        )
+            .chain()
+            .run_if(in_state(MenuState::Controls)),
+    )
+    .add_systems(OnExit(MenuState::Controls), cleanup_menu_system)
+    .add_systems(OnEnter(MenuState::Paused), setup_pause_menu_system)
+    .add_systems(
+        Update,
+        update_menu_system.run_if(in_state(MenuState::Paused)),
+    )
+    .add_systems(OnExit(MenuState::Paused), cleanup_menu_system)
+    .add_systems(OnEnter(MenuState::PausedShowHint), setup_show_hint_system)
+    .add_systems(
+        Update,
+        update_menu_system.run_if(in_state(MenuState::PausedShowHint)),
+    )
+    .add_systems(OnExit(MenuState::PausedShowHint), cleanup_menu_system)
+    .add_systems(OnEnter(MenuState::Success), setup_success_system)
+    .add_systems(
+        Update,
+        update_menu_system.run_if(in_state(MenuState::Success)),
+    )
+    .add_systems(OnExit(MenuState::Success), cleanup_menu_system)
+    .add_systems(OnEnter(MenuState::Failure), setup_failure_system)
+    .add_systems(
+        Update,
+        update_menu_system.run_if(in_state(MenuState::Failure)),
+    )
+    .add_systems(OnExit(MenuState::Failure), cleanup_menu_system)
+    .add_systems(
+        Update,
+        restart_event_system.after(PhaseSet::UpdateBossPhase),
+    )
+    .add_systems(
+        Update,
+        camera_zoom_system
+            .run_if(in_state(MenuState::Unpaused))
+            .before(PhaseSet::UpdatePhase),
+    )
+    .add_systems(
+        PostUpdate,
+        camera_follow_system.before(TransformSystem::TransformPropagate),
+    );
+
+    add_phase_systems(&mut app);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    match replay_from_args() {
+        Some(Ok(playback)) => {
+            app.insert_resource(playback);
+        }
+        Some(Err(err)) => {
+            eprintln!("{}", err);
+            return;
+        }
+        None => {}
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        match net::server_from_args() {
+            Some(Ok(server)) => {
+                app.insert_resource(server);
+            }
+            Some(Err(err)) => {
+                eprintln!("could not host: {}", err);
+                return;
+            }
+            None => {}
+        }
+        net::add_net_server_systems(&mut app);
+    }
+
+    app.run();
 }
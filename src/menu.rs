@@ -3,7 +3,10 @@ use bevy::prelude::*;
 use crate::{
     ai::AiRole,
     audio::{play_sfx, PhaseAudio, Sfx, SfxSource},
-    game::{Game, GameState, MenuState, Player, HEIGHT, WIDTH},
+    game::{Game, GameState, MenuState, Player, GAME_SPEEDS, HEIGHT, WIDTH},
+    input::{InputAction, InputBinding, InputBindings, BINDING_SLOTS},
+    ledger::DamageLedger,
+    settings::save_settings,
 };
 
 #[derive(Component)]
@@ -13,6 +16,7 @@ pub struct MenuContainer;
 pub enum ButtonNextState {
     GoTo(GameState),
     StartContinuous(),
+    Controls(),
     Resume(),
     Restart(),
     Exit(),
@@ -28,7 +32,26 @@ pub enum ButtonOnOff {
     AI(),
     AIBars(),
     Role(),
+    SecondPlayer(),
+    Difficulty(),
+    GameSpeed(),
+    StartTime(),
     Audio(),
+    FollowCamera(),
+}
+
+#[derive(Component)]
+pub enum ButtonControls {
+    /// One binding slot of an action
+    Rebind(InputAction, usize),
+    Reset(),
+}
+
+/// The binding slot waiting for a key or mouse button press
+#[derive(Resource)]
+pub struct RebindTarget {
+    action: InputAction,
+    slot: usize,
 }
 
 #[derive(Event)]
@@ -36,6 +59,10 @@ pub struct RestartEvent {
     game_state: GameState,
 }
 
+/// The "Start At" button cycles through these offsets into a phase
+const START_TIME_STEP: f32 = 10.;
+const START_TIME_MAX: f32 = 150.;
+
 const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
 const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
@@ -154,6 +181,11 @@ pub fn setup_menu_system(
 
                     let phases = vec![
                         ("Sound", ButtonOnOff::Audio(), game.audio_enabled),
+                        (
+                            "Follow Camera",
+                            ButtonOnOff::FollowCamera(),
+                            game.camera_follow_enabled,
+                        ),
                         ("Hints", ButtonOnOff::Hints(), game.hints_enabled),
                         ("Friends", ButtonOnOff::AI(), game.ai_enabled),
                         ("Friend Info", ButtonOnOff::AIBars(), game.ai_bars_enabled),
@@ -171,6 +203,17 @@ pub fn setup_menu_system(
                         ),
                     ];
 
+                    container
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            ButtonNextState::Controls(),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((Text::new("Controls"), text_font.clone(), text_color));
+                        });
+
                     container
                         .spawn((
                             Button,
@@ -187,6 +230,69 @@ pub fn setup_menu_system(
                             ));
                         });
 
+                    container
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            ButtonOnOff::SecondPlayer(),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text(second_player_to_string(&game.second_player_role)),
+                                text_font.clone(),
+                                text_color,
+                            ));
+                        });
+
+                    container
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            ButtonOnOff::Difficulty(),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text(format!("Difficulty: {}", game.difficulty.label())),
+                                text_font.clone(),
+                                text_color,
+                            ));
+                        });
+
+                    container
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            ButtonOnOff::GameSpeed(),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text(game_speed_to_string(game.game_speed)),
+                                text_font.clone(),
+                                text_color,
+                            ));
+                        });
+
+                    container
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            ButtonOnOff::StartTime(),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text(format!(
+                                    "Start At: {}",
+                                    start_time_to_string(game.start_time)
+                                )),
+                                text_font.clone(),
+                                text_color,
+                            ));
+                        });
+
                     for (label, state, onoff_enabled) in phases {
                         container
                             .spawn((
@@ -277,6 +383,271 @@ pub fn setup_pause_menu_system(mut commands: Commands, asset_server: Res<AssetSe
         });
 }
 
+fn rebind_label(bindings: &InputBindings, action: InputAction, slot: usize) -> String {
+    bindings
+        .get(action, slot)
+        .map_or("-".to_string(), |binding| binding.label())
+}
+
+pub fn setup_controls_menu_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bindings: Res<InputBindings>,
+) {
+    let row_height = Val::Px(44.0);
+    let label_width = Val::Px(240.0);
+    let binding_width = Val::Px(180.0);
+
+    let binding_node = Node {
+        width: binding_width,
+        height: row_height,
+        margin: UiRect::all(Val::Px(4.)),
+        // horizontally center child text
+        justify_content: JustifyContent::Center,
+        // vertically center child text
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    let button_node = Node {
+        width: Val::Px(300.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(10.)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    let text_font = TextFont {
+        font: asset_server.load("trebuchet_ms.ttf"),
+        font_size: 28.0,
+        ..default()
+    };
+    let text_color = TextColor(Color::srgb(0.9, 0.9, 0.9));
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Px(WIDTH),
+                height: Val::Px(HEIGHT),
+                flex_direction: FlexDirection::Column,
+                // horizontally center children
+                justify_content: JustifyContent::Center,
+                // vertically center children
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            MenuContainer,
+        ))
+        .with_children(|container| {
+            container.spawn((
+                Text::new("Click a binding then press a key or mouse button.\nEscape cancels, Backspace unbinds."),
+                text_font.clone().with_font_size(20.),
+                text_color,
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    margin: UiRect::bottom(Val::Px(10.)),
+                    ..default()
+                },
+            ));
+
+            for action in InputAction::ALL {
+                container
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Text::new(action.label()),
+                            text_font.clone(),
+                            text_color,
+                            Node {
+                                width: label_width,
+                                ..default()
+                            },
+                        ));
+
+                        for slot in 0..BINDING_SLOTS {
+                            row.spawn((
+                                Button,
+                                binding_node.clone(),
+                                BackgroundColor(NORMAL_BUTTON),
+                                ButtonControls::Rebind(action, slot),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text(rebind_label(&bindings, action, slot)),
+                                    text_font.clone(),
+                                    text_color,
+                                ));
+                            });
+                        }
+                    });
+            }
+
+            container
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        Button,
+                        button_node.clone(),
+                        BackgroundColor(NORMAL_BUTTON),
+                        ButtonControls::Reset(),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((Text::new("Reset"), text_font.clone(), text_color));
+                    });
+
+                    row.spawn((
+                        Button,
+                        button_node.clone(),
+                        BackgroundColor(NORMAL_BUTTON),
+                        ButtonNextState::Exit(),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((Text::new("Back"), text_font.clone(), text_color));
+                    });
+                });
+        });
+}
+
+/// Assigns the next key or mouse button press to the slot being rebound
+pub fn rebind_capture_system(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    target: Option<Res<RebindTarget>>,
+    game: Res<Game>,
+    mut bindings: ResMut<InputBindings>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    let Some(target) = target else {
+        if keyboard_input.just_pressed(KeyCode::Escape) {
+            next_menu_state.set(MenuState::StartMenu);
+        }
+        return;
+    };
+    // Skip the frame of the click that picked the slot
+    if target.is_added() {
+        return;
+    }
+
+    let pressed = keyboard_input
+        .get_just_pressed()
+        .next()
+        .map(|&key| InputBinding::Key(key))
+        .or_else(|| {
+            mouse_button_input
+                .get_just_pressed()
+                .next()
+                .map(|&button| InputBinding::Mouse(button))
+        });
+    let Some(pressed) = pressed else {
+        return;
+    };
+
+    match pressed {
+        InputBinding::Key(KeyCode::Escape) => {}
+        InputBinding::Key(KeyCode::Backspace | KeyCode::Delete) => {
+            bindings.set(target.action, target.slot, None);
+        }
+        binding => {
+            bindings.set(target.action, target.slot, Some(binding));
+        }
+    }
+    commands.remove_resource::<RebindTarget>();
+    save_settings(&game, &bindings);
+}
+
+pub fn update_controls_menu_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    target: Option<Res<RebindTarget>>,
+    game: Res<Game>,
+    mut bindings: ResMut<InputBindings>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &ButtonControls),
+        Changed<Interaction>,
+    >,
+) {
+    // Clicking while waiting binds the mouse button instead
+    let capturing = target.is_some();
+
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                if capturing {
+                    continue;
+                }
+
+                play_sfx(
+                    &mut commands,
+                    &asset_server,
+                    Sfx::MenuClick,
+                    SfxSource::Player,
+                );
+
+                match *button {
+                    ButtonControls::Rebind(action, slot) => {
+                        commands.insert_resource(RebindTarget { action, slot });
+                    }
+                    ButtonControls::Reset() => {
+                        *bindings = InputBindings::default();
+                        save_settings(&game, &bindings);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+pub fn update_rebind_labels_system(
+    bindings: Res<InputBindings>,
+    target: Option<Res<RebindTarget>>,
+    buttons: Query<(&ButtonControls, &Children)>,
+    mut texts: Query<&mut Text>,
+    mut was_capturing: Local<bool>,
+) {
+    // Cancelling a rebind doesn't touch the bindings but still needs a redraw
+    let capturing = target.is_some();
+    let target_added = target.as_ref().is_some_and(|target| target.is_added());
+    if !bindings.is_changed() && !target_added && capturing == *was_capturing {
+        return;
+    }
+    *was_capturing = capturing;
+
+    for (button, children) in &buttons {
+        let ButtonControls::Rebind(action, slot) = *button else {
+            continue;
+        };
+        let waiting = target
+            .as_ref()
+            .is_some_and(|target| target.action == action && target.slot == slot);
+        let label = if waiting {
+            "...".to_string()
+        } else {
+            rebind_label(&bindings, action, slot)
+        };
+        for &child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0 = label.clone();
+            }
+        }
+    }
+}
+
 pub fn update_menu_system(
     game_state: Res<State<GameState>>,
     mut res_next_game_state: ResMut<NextState<GameState>>,
@@ -312,6 +683,9 @@ pub fn update_menu_system(
                         res_next_game_state.set(GameState::PurificationOne);
                         res_next_menu_state.set(MenuState::Unpaused);
                     }
+                    ButtonNextState::Controls() => {
+                        res_next_menu_state.set(MenuState::Controls);
+                    }
                     ButtonNextState::Resume() => {
                         res_next_menu_state.set(MenuState::Unpaused);
                     }
@@ -357,8 +731,25 @@ fn player_role_to_string(role: &Option<AiRole>) -> String {
     }
 }
 
+fn second_player_to_string(role: &Option<AiRole>) -> String {
+    match role {
+        Some(role) => format!("Player 2: {}", role.to_string()),
+        None => "Player 2: Off".to_string(),
+    }
+}
+
+fn game_speed_to_string(game_speed: f32) -> String {
+    format!("Game Speed: {}%", (game_speed * 100.).round())
+}
+
+fn start_time_to_string(start_time: f32) -> String {
+    let secs = start_time as i32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
 pub fn update_menu_onoff_system(
     mut game: ResMut<Game>,
+    bindings: Res<InputBindings>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut global_volume: ResMut<GlobalVolume>,
@@ -494,6 +885,12 @@ pub fn update_menu_onoff_system(
                                 AiRole::Virt1 | AiRole::Virt2 => Some(AiRole::Herald1),
                                 AiRole::Herald1 | AiRole::Herald2 => Some(AiRole::Ham1),
                                 AiRole::Ham1 | AiRole::Ham2 => Some(AiRole::Dps1),
+                                // Player 2 needs someone on the keyboard
+                                AiRole::Dps1 | AiRole::Dps2 | AiRole::Dps3 | AiRole::Dps4
+                                    if game.second_player_role.is_some() =>
+                                {
+                                    Some(AiRole::Virt1)
+                                }
                                 AiRole::Dps1 | AiRole::Dps2 | AiRole::Dps3 | AiRole::Dps4 => None,
                             }
                         } else {
@@ -508,6 +905,79 @@ pub fn update_menu_onoff_system(
                         }
                     }
 
+                    ButtonOnOff::SecondPlayer() => {
+                        // Second slot roles so the two humans never collide
+                        let next_role = match game.second_player_role {
+                            // Nobody to play alongside while spectating
+                            None if game.player_role.is_none() => None,
+                            None => Some(AiRole::Virt2),
+                            Some(AiRole::Virt1 | AiRole::Virt2) => Some(AiRole::Herald2),
+                            Some(AiRole::Herald1 | AiRole::Herald2) => Some(AiRole::Ham2),
+                            Some(AiRole::Ham1 | AiRole::Ham2) => Some(AiRole::Dps2),
+                            Some(AiRole::Dps1 | AiRole::Dps2 | AiRole::Dps3 | AiRole::Dps4) => None,
+                        };
+                        game.second_player_role = next_role;
+
+                        for &child in children.iter() {
+                            if let Ok(mut text) = texts.get_mut(child) {
+                                text.0 = second_player_to_string(&next_role);
+                            }
+                        }
+                    }
+
+                    ButtonOnOff::Difficulty() => {
+                        game.difficulty = game.difficulty.next();
+
+                        for &child in children.iter() {
+                            if let Ok(mut text) = texts.get_mut(child) {
+                                text.0 = format!("Difficulty: {}", game.difficulty.label());
+                            }
+                        }
+                    }
+
+                    ButtonOnOff::GameSpeed() => {
+                        let index = GAME_SPEEDS
+                            .iter()
+                            .position(|speed| *speed == game.game_speed)
+                            .map_or(0, |index| (index + 1) % GAME_SPEEDS.len());
+                        game.game_speed = GAME_SPEEDS[index];
+
+                        for &child in children.iter() {
+                            if let Ok(mut text) = texts.get_mut(child) {
+                                text.0 = game_speed_to_string(game.game_speed);
+                            }
+                        }
+                    }
+
+                    ButtonOnOff::StartTime() => {
+                        game.start_time += START_TIME_STEP;
+                        if game.start_time > START_TIME_MAX {
+                            game.start_time = 0.;
+                        }
+
+                        for &child in children.iter() {
+                            if let Ok(mut text) = texts.get_mut(child) {
+                                text.0 =
+                                    format!("Start At: {}", start_time_to_string(game.start_time));
+                            }
+                        }
+                    }
+
+                    ButtonOnOff::FollowCamera() => {
+                        game.camera_follow_enabled = !game.camera_follow_enabled;
+                        let onoff = if game.camera_follow_enabled {
+                            "ON"
+                        } else {
+                            "OFF"
+                        };
+
+                        for &child in children.iter() {
+                            if let Ok(mut text) = texts.get_mut(child) {
+                                text.0 = format!("Follow Camera: {}", onoff);
+                            }
+                        }
+                    }
+
                     ButtonOnOff::Audio() => {
                         game.audio_enabled = !game.audio_enabled;
                         let onoff = if game.audio_enabled { "ON" } else { "OFF" };
@@ -525,6 +995,8 @@ pub fn update_menu_onoff_system(
                         }
                     }
                 }
+
+                save_settings(&game, &bindings);
             }
             Interaction::Hovered => {
                 *color = HOVERED_BUTTON.into();
@@ -596,7 +1068,7 @@ fn setup_result_screen(
         .spawn((
             Node {
                 width: Val::Px(WIDTH),
-                height: Val::Px(HEIGHT / 2.),
+                min_height: Val::Px(HEIGHT / 2.),
                 margin: UiRect::all(Val::Auto), // UiRect::new(Val::Px(0.), Val::Px(0.), Val::Px(0.), Val::Px(HEIGHT / 4.)),
                 // horizontally center child text
                 justify_content: JustifyContent::Center,
@@ -637,18 +1109,35 @@ fn setup_result_screen(
                     let milliseconds =
                         ((game.time_elapsed.elapsed_secs() % 1.) * 1000.).floor() as i32;
 
-                    let time_str = format!("{}:{:02}.{:03}", minutes, seconds, milliseconds);
+                    let mut time_str = format!("{}:{:02}.{:03}", minutes, seconds, milliseconds);
+                    if let Some(label) = game.game_speed_label() {
+                        time_str = format!("{} ({})", time_str, label);
+                    }
                     parent.spawn((Text::new(result_message), text_font.clone(), text_color));
                     parent.spawn((
                         Text(format!("\nTime: {}\n", time_str)),
                         text_font_small.clone(),
                         text_color,
                     ));
-                    parent.spawn((
-                        Text(format!("Damage Taken: {}", game.player_damage_taken as i32)),
-                        text_font_small.clone(),
-                        text_color,
-                    ));
+                });
+
+            big_container
+                .spawn((
+                    Node {
+                        width: Val::Px(WIDTH),
+                        padding: UiRect::all(Val::Px(10.)),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0., 0., 0., 0.6)),
+                ))
+                .with_children(|parent| {
+                    let text_font = TextFont {
+                        font: asset_server.load("trebuchet_ms.ttf"),
+                        font_size: 16.,
+                        ..default()
+                    };
+                    spawn_damage_table(parent, &game.damage_ledger, text_font, text_color);
                 });
 
             big_container
@@ -683,6 +1172,62 @@ fn setup_result_screen(
         });
 }
 
+/// Damage per mechanic (rows) and per player (columns)
+fn spawn_damage_table(
+    parent: &mut ChildBuilder,
+    ledger: &DamageLedger,
+    text_font: TextFont,
+    text_color: TextColor,
+) {
+    let mechanics = ledger.mechanics();
+    let victims = ledger.victims();
+
+    if mechanics.is_empty() {
+        parent.spawn((Text::new("No damage taken"), text_font, text_color));
+        return;
+    }
+
+    parent
+        .spawn(Node {
+            display: Display::Grid,
+            grid_template_columns: RepeatedGridTrack::auto(victims.len() as u16 + 2),
+            column_gap: Val::Px(12.),
+            ..default()
+        })
+        .with_children(|table| {
+            let cell = |table: &mut ChildBuilder, text: String| {
+                table.spawn((Text(text), text_font.clone(), text_color));
+            };
+            let amount = |amount: f32| {
+                if amount > 0. {
+                    format!("{}", amount.round() as i32)
+                } else {
+                    "-".to_string()
+                }
+            };
+
+            cell(table, "Damage Taken".to_string());
+            for victim in &victims {
+                cell(table, victim.to_string());
+            }
+            cell(table, "Total".to_string());
+
+            for mechanic in &mechanics {
+                cell(table, mechanic.to_string());
+                for victim in &victims {
+                    cell(table, amount(ledger.total(Some(mechanic), Some(victim))));
+                }
+                cell(table, amount(ledger.total(Some(mechanic), None)));
+            }
+
+            cell(table, "Total".to_string());
+            for victim in &victims {
+                cell(table, amount(ledger.total(None, Some(victim))));
+            }
+            cell(table, amount(ledger.total(None, None)));
+        });
+}
+
 pub fn setup_failure_system(
     game: Res<Game>,
     mut commands: Commands,
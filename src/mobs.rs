@@ -4,10 +4,11 @@ use core::f32::consts::PI;
 
 use std::ops::{Add, Sub};
 
-use crate::aoes::{spawn_aoe, Aoe, AoeDesc};
+use crate::aoes::{spawn_aoe, spawn_oriented_aoe, Aoe, AoeDesc};
 use crate::audio::{play_sfx, Sfx, SfxSource};
-use crate::collisions::CollisionRadius;
+use crate::collisions::{CollisionRadius, CollisionShape};
 use crate::game::*;
+use crate::ledger::DamageSource;
 use crate::phase::{EffectForcedMarch, Velocity};
 
 pub const BOSS_RADIUS: f32 = 420. * GAME_TO_PX;
@@ -16,6 +17,10 @@ pub const BIGBOY_RADIUS: f32 = 120. * GAME_TO_PX;
 pub const NOODLE_RADIUS: f32 = 80. * GAME_TO_PX;
 pub const NOODLE_SLAM_RADIUS: f32 = 540. * GAME_TO_PX;
 
+/// The laser's wiggle is approximated by this many straight capsules
+const SALTSPRAY_LASER_SEGMENTS: usize = 8;
+const SALTSPRAY_LASER_STEPS: usize = 32;
+
 pub const ECHO_RADIUS: f32 = 160. * GAME_TO_PX;
 pub const ECHO_SPEED: f32 = 160.;
 pub const ECHO_DAMAGE: f32 = 5.;
@@ -161,6 +166,7 @@ pub fn goliath_system(
                     damage: GOLIATH_BULLET_DAMAGE,
                     knockback: GOLIATH_BULLET_KNOCKBACK,
                 },
+                DamageSource("goliath shot"),
                 CollisionRadius(bullet_radius),
                 PhaseEntity,
             ));
@@ -209,6 +215,7 @@ pub fn wyvern_system(
                     damage: WYVERN_BULLET_DAMAGE,
                     knockback: 0.,
                 },
+                DamageSource("wyvern shot"),
                 CollisionRadius(BULLET_SIZE / 2.),
                 PhaseEntity,
             ));
@@ -246,6 +253,7 @@ pub fn wyvern_system(
                         damage: WYVERN_BULLET_DAMAGE,
                         knockback: 80. * GAME_TO_PX,
                     },
+                    DamageSource("wyvern shockwave"),
                     CollisionRadius(bullet_radius),
                     PhaseEntity,
                 ));
@@ -308,6 +316,7 @@ pub fn saltspray_system(
     time: Res<Time>,
     asset_server: Res<AssetServer>,
     mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
     mut saltsprays: Query<(&mut MobSaltspray, &Transform)>,
     players: Query<&Transform, With<Player>>,
 ) {
@@ -327,18 +336,39 @@ pub fn saltspray_system(
             let backbone = to_player.clamp_length(300., 300.);
             let perp = Vec3::new(-backbone.y, backbone.x, 0.);
 
-            for i in 0..32 {
-                let magnitude = i as f32 / 32.;
+            let laser_pos = |i: usize| {
+                let magnitude = i as f32 / SALTSPRAY_LASER_STEPS as f32;
                 let amount = ((i as f32) / 5.).sin();
 
                 let mut pos = transform.translation + backbone * magnitude;
                 pos = pos.add(perp * amount * magnitude);
                 pos.z = LAYER_AOE;
-
-                spawn_aoe(
+                pos
+            };
+
+            let step = SALTSPRAY_LASER_STEPS / SALTSPRAY_LASER_SEGMENTS;
+            for i in (0..SALTSPRAY_LASER_STEPS).step_by(step) {
+                let magnitude = i as f32 / SALTSPRAY_LASER_STEPS as f32;
+                let start = laser_pos(i);
+                let end = laser_pos(i + step);
+                let along = end.sub(start).truncate();
+
+                let shape = CollisionShape::Capsule {
+                    half_length: along.length() / 2.,
+                    radius: mob.aoe_desc.radius,
+                };
+                let aoe_desc = AoeDesc {
+                    mesh: meshes.add(shape.mesh()),
+                    radius: shape.bounding_radius(),
+                    shape: Some(shape),
+                    ..mob.aoe_desc.clone()
+                };
+
+                spawn_oriented_aoe(
                     &mut commands,
-                    &mob.aoe_desc,
-                    pos,
+                    &aoe_desc,
+                    start.add(end) / 2.,
+                    Quat::from_rotation_arc_2d(Vec2::Y, along.normalize_or(Vec2::Y)),
                     Aoe {
                         visibility_start: Some(Timer::from_seconds(
                             magnitude / 2.,
@@ -400,6 +430,7 @@ pub fn timecaster_system(
                         damage: TIMECASTER_BULLET_DAMAGE,
                         knockback: 10.,
                     },
+                    DamageSource("timecaster shot"),
                     CollisionRadius(BULLET_SIZE / 2.),
                     PhaseEntity,
                 ));
@@ -0,0 +1,1093 @@
+use bevy::{
+    ecs::query::QueryData,
+    input::InputSystem,
+    prelude::*,
+    sprite::Anchor,
+    window::{WindowCloseRequested, WindowResolution},
+};
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::ai::{AiPlayer, AiRole};
+use crate::aoes::{Aoe, Soup, AOE_BASE_COLOR};
+use crate::boss_phase::{Puddle, PUDDLE_RADIUS};
+use crate::camera::{camera_follow_system, hud_layer, spawn_cameras};
+use crate::collisions::{CollisionArea, CollisionAreaItem, CollisionRadius, CollisionShape};
+use crate::downed::Downed;
+use crate::frost_beams::FrostBeam;
+use crate::game::*;
+use crate::greens::{StackGreen, GREEN_RADIUS};
+use crate::input::*;
+use crate::mobs::{Boss, Enemy};
+use crate::phase::icon_for_role;
+use crate::replay::ReplayPlayback;
+use crate::roster::{PlayerHandOver, RosterChange};
+use crate::settings::load_settings;
+use crate::waves::{wave_shape, Wave, WAVE_MAX_RADIUS};
+
+pub const DEFAULT_PORT: u16 = 7777;
+/// Largest payload a single UDP datagram can carry
+const MAX_DATAGRAM: usize = 65507;
+/// Clients that have sent nothing for this long are dropped until they rejoin
+const CLIENT_TIMEOUT_S: f32 = 5.;
+/// How often a client asks to join until the host answers
+const JOIN_RETRY_S: f32 = 1.;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Join {
+        role: AiRole,
+    },
+    /// Sent every frame once joined
+    Input(HumanInput),
+    Leave,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ServerMessage {
+    Welcome { role: AiRole },
+    Rejected(String),
+    Snapshot(Snapshot),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub role: AiRole,
+    pub position: Vec2,
+    pub hp: f32,
+    pub downed: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ShapeKind {
+    Boss,
+    Enemy,
+    Bullet,
+    Aoe,
+    Wave,
+    Green,
+    Puddle,
+    Soup,
+    /// Where a frost beam is about to sweep
+    FrostBeamPath,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShapeState {
+    pub kind: ShapeKind,
+    pub position: Vec2,
+    /// Radians counterclockwise, for shapes that have a facing
+    pub rotation: f32,
+    /// Circle of this radius unless it has a shape
+    pub radius: f32,
+    pub shape: Option<CollisionShape>,
+}
+
+/// Everything a client needs to draw one frame of the host's phase
+#[derive(Resource, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Counts up every frame so clients can drop datagrams that arrive late
+    pub sequence: u64,
+    pub game_state: GameState,
+    pub time_elapsed: f32,
+    pub players: Vec<PlayerState>,
+    pub shapes: Vec<ShapeState>,
+}
+
+/// Varint bincode keeps a busy snapshot to a few dozen bytes per shape. The
+/// limit stops a bad length from allocating more than a datagram could hold
+fn encoding() -> impl Options {
+    bincode::DefaultOptions::new().with_limit(MAX_DATAGRAM as u64)
+}
+
+fn encode<T: Serialize>(message: &T) -> bincode::Result<Vec<u8>> {
+    encoding().serialize(message)
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> bincode::Result<T> {
+    encoding().deserialize(bytes)
+}
+
+fn send<T: Serialize>(socket: &UdpSocket, addr: SocketAddr, message: &T) {
+    let contents = match encode(message) {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!("could not serialize message: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = socket.send_to(&contents, addr) {
+        warn!("could not send to {}: {}", addr, err);
+    }
+}
+
+/// Everything that arrived since the last call, skipping anything unreadable
+fn receive<T: DeserializeOwned>(socket: &UdpSocket) -> Vec<(SocketAddr, T)> {
+    let mut messages = vec![];
+    let mut buf = vec![0; MAX_DATAGRAM];
+    loop {
+        match socket.recv_from(&mut buf) {
+            Ok((len, addr)) => match decode(&buf[..len]) {
+                Ok(message) => messages.push((addr, message)),
+                Err(err) => warn!("could not parse message from {}: {}", addr, err),
+            },
+            Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+            // Windows reports a client going away as an error on the next read
+            Err(err) if err.kind() == ErrorKind::ConnectionReset => continue,
+            Err(err) => {
+                warn!("could not receive: {}", err);
+                break;
+            }
+        }
+    }
+    messages
+}
+
+fn parse_role(name: &str) -> Option<AiRole> {
+    let name = name.to_lowercase();
+    AiRole::ALL
+        .into_iter()
+        .find(|role| format!("{:?}", role).to_lowercase() == name)
+}
+
+struct RemoteClient {
+    addr: SocketAddr,
+    role: AiRole,
+    /// Index into `game.remote_player_roles`
+    slot: usize,
+    silent_secs: f32,
+    /// Latest input, with every just pressed action since the last frame
+    input: HumanInput,
+}
+
+/// Hosts the authoritative phase for humans joined over UDP. A client in slot
+/// `i` plays [`HumanPlayer`] index `LOCAL_PLAYERS + i`
+#[derive(Resource)]
+pub struct NetServer {
+    socket: UdpSocket,
+    clients: Vec<RemoteClient>,
+}
+
+impl NetServer {
+    pub fn bind(port: u16) -> std::io::Result<NetServer> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(NetServer {
+            socket,
+            clients: vec![],
+        })
+    }
+
+    fn join(
+        &mut self,
+        game: &Game,
+        addr: SocketAddr,
+        role: AiRole,
+    ) -> (ServerMessage, Option<RosterChange>) {
+        // Answering a retried join
+        if let Some(client) = self.clients.iter_mut().find(|client| client.addr == addr) {
+            if client.role != role {
+                let reason = format!("already joined as {:?}", client.role);
+                return (ServerMessage::Rejected(reason), None);
+            }
+            client.silent_secs = 0.;
+            return (ServerMessage::Welcome { role }, None);
+        }
+
+        if self.clients.iter().any(|client| client.role == role) {
+            let reason = format!("{:?} is already taken", role);
+            return (ServerMessage::Rejected(reason), None);
+        }
+
+        if game
+            .local_player_roles()
+            .iter()
+            .any(|(_, local_role)| *local_role == role)
+        {
+            let reason = format!("{:?} is played on the host", role);
+            return (ServerMessage::Rejected(reason), None);
+        }
+
+        info!("{} joined as {:?}", addr, role);
+        // Slots of players who left are reused, but never renumbered
+        let slot = game
+            .remote_player_roles
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or(game.remote_player_roles.len());
+        self.clients.push(RemoteClient {
+            addr,
+            role,
+            slot,
+            silent_secs: 0.,
+            input: HumanInput::default(),
+        });
+        let change = RosterChange::Joined {
+            index: LOCAL_PLAYERS + slot,
+            role,
+        };
+        (ServerMessage::Welcome { role }, Some(change))
+    }
+
+    fn drop_client(&mut self, i: usize) -> RosterChange {
+        let client = self.clients.remove(i);
+        RosterChange::Left {
+            index: LOCAL_PLAYERS + client.slot,
+        }
+    }
+}
+
+/// Starts hosting if run with `--host [port]`
+pub fn server_from_args() -> Option<std::io::Result<NetServer>> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--host")?;
+    let port = args
+        .get(index + 1)
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(DEFAULT_PORT);
+    Some(NetServer::bind(port))
+}
+
+fn net_server_receive_system(
+    real_time: Res<Time<Real>>,
+    mut server: ResMut<NetServer>,
+    mut game: ResMut<Game>,
+    mut inputs: ResMut<HumanInputs>,
+    mut hand_over: PlayerHandOver,
+) {
+    for client in server.clients.iter_mut() {
+        client.silent_secs += real_time.delta_secs();
+    }
+    while let Some(i) = server
+        .clients
+        .iter()
+        .position(|client| client.silent_secs > CLIENT_TIMEOUT_S)
+    {
+        info!("{:?} timed out", server.clients[i].role);
+        let change = server.drop_client(i);
+        hand_over.hand_over_players(&mut game, change);
+    }
+
+    for (addr, message) in receive::<ClientMessage>(&server.socket) {
+        match message {
+            ClientMessage::Join { role } => {
+                let (reply, change) = server.join(&game, addr, role);
+                if let Some(change) = change {
+                    hand_over.hand_over_players(&mut game, change);
+                }
+                send(&server.socket, addr, &reply);
+            }
+            ClientMessage::Input(input) => {
+                let Some(client) = server.clients.iter_mut().find(|client| client.addr == addr)
+                else {
+                    continue;
+                };
+                client.silent_secs = 0.;
+                // A press that arrives between frames must still be seen once
+                let just_pressed = std::mem::take(&mut client.input.just_pressed);
+                client.input = input;
+                client.input.just_pressed.extend(just_pressed);
+            }
+            ClientMessage::Leave => {
+                if let Some(i) = server.clients.iter().position(|client| client.addr == addr) {
+                    info!("{:?} left", server.clients[i].role);
+                    let change = server.drop_client(i);
+                    hand_over.hand_over_players(&mut game, change);
+                }
+            }
+        }
+    }
+
+    // Empty slots stay in place so nobody's index shifts mid-run
+    inputs.ensure_len(LOCAL_PLAYERS + game.remote_player_roles.len());
+    for input in inputs.0.iter_mut().skip(LOCAL_PLAYERS) {
+        *input = HumanInput::default();
+    }
+    for client in server.clients.iter_mut() {
+        inputs.0[LOCAL_PLAYERS + client.slot] = client.input.clone();
+        client.input.just_pressed.clear();
+    }
+}
+
+#[derive(QueryData)]
+struct SnapshotPlayer {
+    transform: &'static Transform,
+    player: &'static Player,
+    ai_player: Option<&'static AiPlayer>,
+    human_player: Option<&'static HumanPlayer>,
+    downed: Has<Downed>,
+}
+
+fn snapshot_players_system(
+    game: Res<Game>,
+    game_state: Res<State<GameState>>,
+    players: Query<SnapshotPlayer>,
+    mut snapshot: ResMut<Snapshot>,
+) {
+    snapshot.sequence += 1;
+    snapshot.game_state = *game_state.get();
+    snapshot.time_elapsed = game.time_elapsed.elapsed_secs();
+    snapshot.players.clear();
+    for player in &players {
+        let Some(role) = player
+            .ai_player
+            .map(|ai_player| ai_player.role)
+            .or(player.human_player.map(|human_player| human_player.role))
+        else {
+            continue;
+        };
+        snapshot.players.push(PlayerState {
+            role,
+            position: player.transform.translation.truncate(),
+            hp: player.player.get_hp(),
+            downed: player.downed,
+        });
+    }
+}
+
+fn shape_state(kind: ShapeKind, transform: &Transform, radius: f32) -> ShapeState {
+    ShapeState {
+        kind,
+        position: transform.translation.truncate(),
+        rotation: transform.rotation.to_euler(EulerRot::ZYX).0,
+        radius: radius * transform.scale.x,
+        shape: None,
+    }
+}
+
+/// Shapes are already sized in world space, so unlike circles they ignore scale
+fn area_state(kind: ShapeKind, area: &CollisionAreaItem) -> ShapeState {
+    match area.shape {
+        Some(shape) => ShapeState {
+            radius: shape.bounding_radius(),
+            shape: Some(*shape),
+            ..shape_state(kind, area.transform, 1.)
+        },
+        None => shape_state(kind, area.transform, area.radius.0),
+    }
+}
+
+/// The legs of a frost beam's path, laid out the same as its telegraph
+fn frost_beam_path_states(beam: &FrostBeam, radius: f32) -> impl Iterator<Item = ShapeState> + '_ {
+    beam.path.windows(2).map(move |leg| {
+        let (from, to) = (leg[0].truncate(), leg[1].truncate());
+        let dir = (to - from).normalize_or(Vec2::Y);
+        ShapeState {
+            kind: ShapeKind::FrostBeamPath,
+            position: (from + to) / 2.,
+            rotation: Vec2::Y.angle_to(dir),
+            radius: from.distance(to) / 2. + radius,
+            shape: Some(CollisionShape::Capsule {
+                half_length: from.distance(to) / 2.,
+                radius,
+            }),
+        }
+    })
+}
+
+#[derive(QueryData)]
+struct SnapshotMob {
+    transform: &'static Transform,
+    radius: Option<&'static CollisionRadius>,
+    boss: Has<Boss>,
+}
+
+fn snapshot_mobs_system(
+    enemies: Query<SnapshotMob, With<Enemy>>,
+    bullets: Query<(&Transform, &CollisionRadius), With<EnemyBullet>>,
+    mut snapshot: ResMut<Snapshot>,
+) {
+    snapshot.shapes.clear();
+    for enemy in &enemies {
+        let kind = if enemy.boss {
+            ShapeKind::Boss
+        } else {
+            ShapeKind::Enemy
+        };
+        let radius = enemy.radius.map_or(PLAYER_RADIUS, |radius| radius.0);
+        snapshot
+            .shapes
+            .push(shape_state(kind, enemy.transform, radius));
+    }
+    for (transform, radius) in &bullets {
+        snapshot
+            .shapes
+            .push(shape_state(ShapeKind::Bullet, transform, radius.0));
+    }
+}
+
+#[derive(QueryData)]
+struct SnapshotSoup {
+    area: CollisionArea,
+    visibility: &'static Visibility,
+    frost_beam: Option<&'static FrostBeam>,
+}
+
+fn snapshot_shapes_system(
+    aoes: Query<(CollisionArea, &Visibility), With<Aoe>>,
+    waves: Query<(&Transform, &Visibility), With<Wave>>,
+    greens: Query<(&Transform, &Visibility), With<StackGreen>>,
+    puddles: Query<&Transform, With<Puddle>>,
+    soups: Query<SnapshotSoup, (With<Soup>, Without<Puddle>)>,
+    mut snapshot: ResMut<Snapshot>,
+) {
+    for (area, visibility) in &aoes {
+        if visibility != Visibility::Hidden {
+            snapshot.shapes.push(area_state(ShapeKind::Aoe, &area));
+        }
+    }
+    for (transform, visibility) in &waves {
+        if visibility != Visibility::Hidden {
+            snapshot.shapes.push(ShapeState {
+                radius: WAVE_MAX_RADIUS * transform.scale.x,
+                shape: Some(wave_shape(transform)),
+                ..shape_state(ShapeKind::Wave, transform, 1.)
+            });
+        }
+    }
+    for (transform, visibility) in &greens {
+        if visibility != Visibility::Hidden {
+            snapshot
+                .shapes
+                .push(shape_state(ShapeKind::Green, transform, GREEN_RADIUS));
+        }
+    }
+    for transform in &puddles {
+        snapshot
+            .shapes
+            .push(shape_state(ShapeKind::Puddle, transform, PUDDLE_RADIUS));
+    }
+    for soup in &soups {
+        match soup.frost_beam {
+            Some(beam) if !beam.is_telegraphed() => continue,
+            Some(beam) if !beam.start.finished() => {
+                let paths = frost_beam_path_states(beam, soup.area.radius.0);
+                snapshot.shapes.extend(paths);
+            }
+            Some(_) => {}
+            None if soup.visibility == Visibility::Hidden => continue,
+            None => {}
+        }
+        snapshot
+            .shapes
+            .push(area_state(ShapeKind::Soup, &soup.area));
+    }
+}
+
+fn net_server_send_system(server: Res<NetServer>, snapshot: Res<Snapshot>) {
+    let message = ServerMessage::Snapshot(snapshot.clone());
+    for client in &server.clients {
+        send(&server.socket, client.addr, &message);
+    }
+}
+
+pub fn add_net_server_systems(app: &mut App) {
+    app.init_resource::<Snapshot>()
+        .add_systems(
+            PreUpdate,
+            net_server_receive_system
+                .after(InputSystem)
+                .after(read_gamepad_input_system)
+                .run_if(resource_exists::<NetServer>)
+                .run_if(not(resource_exists::<ReplayPlayback>)),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                snapshot_players_system,
+                snapshot_mobs_system,
+                snapshot_shapes_system,
+                net_server_send_system,
+            )
+                .chain()
+                .run_if(resource_exists::<NetServer>)
+                .run_if(in_state(MenuState::Unpaused)),
+        );
+}
+
+enum ClientStatus {
+    Joining,
+    Playing,
+    Rejected(String),
+}
+
+#[derive(Resource)]
+struct NetClient {
+    socket: UdpSocket,
+    host: SocketAddr,
+    role: AiRole,
+    status: ClientStatus,
+    join_retry: Timer,
+}
+
+/// The newest snapshot from the host, if one has arrived
+#[derive(Resource, Default)]
+struct LatestSnapshot(Option<Snapshot>);
+
+#[derive(Resource)]
+struct SnapshotAssets {
+    circle: Handle<Mesh>,
+    /// Meshes for the shapes seen so far
+    shapes: HashMap<ShapeKey, Handle<Mesh>>,
+    boss: Handle<ColorMaterial>,
+    enemy: Handle<ColorMaterial>,
+    bullet: Handle<ColorMaterial>,
+    aoe: Handle<ColorMaterial>,
+    wave: Handle<ColorMaterial>,
+    green: Handle<ColorMaterial>,
+    puddle: Handle<ColorMaterial>,
+    soup: Handle<ColorMaterial>,
+    frost_beam_path: Handle<ColorMaterial>,
+}
+
+/// Growing waves and void zones make a new shape every frame, so the cache
+/// starts over rather than keeping all of them
+const MAX_CACHED_SHAPES: usize = 256;
+
+impl SnapshotAssets {
+    fn material(&self, kind: ShapeKind) -> Handle<ColorMaterial> {
+        match kind {
+            ShapeKind::Boss => self.boss.clone(),
+            ShapeKind::Enemy => self.enemy.clone(),
+            ShapeKind::Bullet => self.bullet.clone(),
+            ShapeKind::Aoe => self.aoe.clone(),
+            ShapeKind::Wave => self.wave.clone(),
+            ShapeKind::Green => self.green.clone(),
+            ShapeKind::Puddle => self.puddle.clone(),
+            ShapeKind::Soup => self.soup.clone(),
+            ShapeKind::FrostBeamPath => self.frost_beam_path.clone(),
+        }
+    }
+
+    fn mesh(&mut self, meshes: &mut Assets<Mesh>, shape: &CollisionShape) -> Handle<Mesh> {
+        if self.shapes.len() > MAX_CACHED_SHAPES {
+            self.shapes.clear();
+        }
+        self.shapes
+            .entry(ShapeKey::from(shape))
+            .or_insert_with(|| meshes.add(shape.mesh()))
+            .clone()
+    }
+
+    fn shape_parts(
+        &mut self,
+        meshes: &mut Assets<Mesh>,
+        shape: &ShapeState,
+    ) -> (Mesh2d, MeshMaterial2d<ColorMaterial>, Transform) {
+        let layer = match shape.kind {
+            ShapeKind::Boss | ShapeKind::Enemy => LAYER_MOB,
+            ShapeKind::Bullet => LAYER_BULLET,
+            ShapeKind::Wave => LAYER_WAVE,
+            ShapeKind::Soup => LAYER_ROTATING_SOUP,
+            ShapeKind::FrostBeamPath => LAYER_ROTATING_SOUP - 0.1,
+            ShapeKind::Aoe | ShapeKind::Green | ShapeKind::Puddle => LAYER_AOE,
+        };
+        let (mesh, scale) = match &shape.shape {
+            Some(collision_shape) => (self.mesh(meshes, collision_shape), 1.),
+            None => (self.circle.clone(), shape.radius),
+        };
+        let transform = Transform::from_translation(shape.position.extend(layer))
+            .with_rotation(Quat::from_rotation_z(shape.rotation))
+            .with_scale(Vec3::splat(scale));
+        (
+            Mesh2d(mesh),
+            MeshMaterial2d(self.material(shape.kind)),
+            transform,
+        )
+    }
+}
+
+/// A [`CollisionShape`] with its floats as bits so it can key a map
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum ShapeKey {
+    Cone {
+        radius: u32,
+        half_angle: u32,
+    },
+    Rect {
+        half_size: [u32; 2],
+    },
+    Capsule {
+        half_length: u32,
+        radius: u32,
+    },
+    Donut {
+        inner_radius: u32,
+        outer_radius: u32,
+    },
+}
+
+impl From<&CollisionShape> for ShapeKey {
+    fn from(shape: &CollisionShape) -> ShapeKey {
+        match *shape {
+            CollisionShape::Cone { radius, half_angle } => ShapeKey::Cone {
+                radius: radius.to_bits(),
+                half_angle: half_angle.to_bits(),
+            },
+            CollisionShape::Rect { half_size } => ShapeKey::Rect {
+                half_size: half_size.to_array().map(f32::to_bits),
+            },
+            CollisionShape::Capsule {
+                half_length,
+                radius,
+            } => ShapeKey::Capsule {
+                half_length: half_length.to_bits(),
+                radius: radius.to_bits(),
+            },
+            CollisionShape::Donut {
+                inner_radius,
+                outer_radius,
+            } => ShapeKey::Donut {
+                inner_radius: inner_radius.to_bits(),
+                outer_radius: outer_radius.to_bits(),
+            },
+        }
+    }
+}
+
+/// Draws the shape at this index in the latest snapshot
+#[derive(Component)]
+struct ShownShape(usize);
+
+/// Draws the player at this index in the latest snapshot
+#[derive(Component)]
+struct ShownPlayer {
+    index: usize,
+    role: AiRole,
+}
+
+#[derive(Component)]
+struct ClientStatusText;
+
+/// Joins a host if run with `--join <address> [--role <role>]`, returning
+/// false if the game should start normally instead
+pub fn run_client_from_args() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(join_index) = args.iter().position(|arg| arg == "--join") else {
+        return false;
+    };
+
+    let Some(host) = args.get(join_index + 1).and_then(|host| {
+        let host = if host.contains(':') {
+            host.clone()
+        } else {
+            format!("{}:{}", host, DEFAULT_PORT)
+        };
+        host.to_socket_addrs().ok()?.next()
+    }) else {
+        eprintln!(
+            "--join expects a host address like 127.0.0.1:{}",
+            DEFAULT_PORT
+        );
+        return true;
+    };
+
+    let role = match args
+        .iter()
+        .position(|arg| arg == "--role")
+        .and_then(|i| args.get(i + 1))
+    {
+        None => AiRole::Virt2,
+        Some(name) => match parse_role(name) {
+            Some(role) => role,
+            None => {
+                let roles: Vec<String> = AiRole::ALL
+                    .iter()
+                    .map(|role| format!("{:?}", role))
+                    .collect();
+                eprintln!("--role expects one of: {}", roles.join(", "));
+                return true;
+            }
+        },
+    };
+
+    let socket = match UdpSocket::bind(("0.0.0.0", 0)).and_then(|socket| {
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    }) {
+        Ok(socket) => socket,
+        Err(err) => {
+            eprintln!("could not open a socket: {}", err);
+            return true;
+        }
+    };
+
+    let mut game = Game::default();
+    let mut bindings = InputBindings::default();
+    if let Some(settings) = load_settings() {
+        settings.apply(&mut game, &mut bindings);
+    }
+    // The one human here gets the keyboard, mouse and gamepad
+    game.second_player_role = None;
+
+    let mut join_retry = Timer::from_seconds(JOIN_RETRY_S, TimerMode::Repeating);
+    join_retry.set_elapsed(join_retry.duration());
+
+    App::new()
+        .add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                title: format!("dragonfruitvoid - {:?} on {}", role, host),
+                resolution: WindowResolution::new(WIDTH, HEIGHT).with_scale_factor_override(1.),
+                ..default()
+            }),
+            ..default()
+        }))
+        .insert_resource(game)
+        .insert_resource(bindings)
+        .insert_resource(ClearColor(Color::srgb(0.3, 0.3, 0.3)))
+        .insert_resource(NetClient {
+            socket,
+            host,
+            role,
+            status: ClientStatus::Joining,
+            join_retry,
+        })
+        .init_resource::<HumanInputs>()
+        .init_resource::<LatestSnapshot>()
+        .add_systems(Startup, setup_client)
+        .add_systems(
+            PreUpdate,
+            (read_human_input_system, read_gamepad_input_system)
+                .chain()
+                .after(InputSystem),
+        )
+        .add_systems(
+            Update,
+            (
+                net_client_system,
+                snapshot_shapes_render_system,
+                snapshot_players_render_system,
+                client_status_system,
+                client_leave_system,
+            )
+                .chain(),
+        )
+        .add_systems(
+            PostUpdate,
+            camera_follow_system.before(TransformSystem::TransformPropagate),
+        )
+        .run();
+
+    true
+}
+
+fn setup_client(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    spawn_cameras(&mut commands);
+
+    commands.spawn((
+        Sprite {
+            image: asset_server.load("map.png"),
+            ..default()
+        },
+        Transform::from_xyz(0., 0., LAYER_MAP),
+    ));
+
+    commands.spawn((
+        Text2d::new(""),
+        TextFont {
+            font: asset_server.load("trebuchet_ms.ttf"),
+            font_size: 24.,
+            ..default()
+        },
+        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        Anchor::TopLeft,
+        Transform::from_xyz(-WIDTH / 2. + 10., HEIGHT / 2. - 10., LAYER_TEXT),
+        ClientStatusText,
+        hud_layer(),
+    ));
+
+    commands.insert_resource(SnapshotAssets {
+        circle: meshes.add(Circle::new(1.)),
+        shapes: HashMap::new(),
+        boss: materials.add(ColorMaterial::from(Color::srgb(0.3, 0.1, 0.4))),
+        enemy: materials.add(ColorMaterial::from(Color::srgb(0.5, 0.2, 0.6))),
+        bullet: materials.add(ColorMaterial::from(Color::srgb(0.9, 0.4, 0.9))),
+        aoe: materials.add(ColorMaterial::from(AOE_BASE_COLOR)),
+        wave: materials.add(ColorMaterial::from(Color::srgba(0.4, 0.6, 0.9, 0.3))),
+        green: materials.add(ColorMaterial::from(Color::srgba(0., 0.9, 0., 0.4))),
+        puddle: materials.add(ColorMaterial::from(Color::srgba(0.9, 0., 0., 0.3))),
+        soup: materials.add(ColorMaterial::from(Color::srgba(0.6, 0., 0.6, 0.4))),
+        frost_beam_path: materials.add(ColorMaterial::from(Color::srgba(0.6, 0.85, 1.0, 0.3))),
+    });
+}
+
+fn net_client_system(
+    time: Res<Time>,
+    inputs: Res<HumanInputs>,
+    mut client: ResMut<NetClient>,
+    mut latest: ResMut<LatestSnapshot>,
+) {
+    match client.status {
+        ClientStatus::Joining => {
+            client.join_retry.tick(time.delta());
+            if client.join_retry.just_finished() {
+                let role = client.role;
+                send(&client.socket, client.host, &ClientMessage::Join { role });
+            }
+        }
+        ClientStatus::Playing => {
+            send(
+                &client.socket,
+                client.host,
+                &ClientMessage::Input(inputs.0[0].clone()),
+            );
+        }
+        ClientStatus::Rejected(_) => {}
+    }
+
+    for (addr, message) in receive::<ServerMessage>(&client.socket) {
+        if addr != client.host {
+            continue;
+        }
+        match message {
+            ServerMessage::Welcome { .. } => client.status = ClientStatus::Playing,
+            ServerMessage::Rejected(reason) => client.status = ClientStatus::Rejected(reason),
+            ServerMessage::Snapshot(snapshot) => {
+                let newer = latest
+                    .0
+                    .as_ref()
+                    .is_none_or(|latest| snapshot.sequence > latest.sequence);
+                if newer {
+                    latest.0 = Some(snapshot);
+                }
+            }
+        }
+    }
+}
+
+/// The parts of a shape entity that follow its place in the snapshot
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct ShownShapeParts {
+    entity: Entity,
+    shown: &'static ShownShape,
+    mesh: &'static mut Mesh2d,
+    material: &'static mut MeshMaterial2d<ColorMaterial>,
+    transform: &'static mut Transform,
+}
+
+fn snapshot_shapes_render_system(
+    mut commands: Commands,
+    latest: Res<LatestSnapshot>,
+    mut assets: ResMut<SnapshotAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut shown_shapes: Query<ShownShapeParts, Without<ShownPlayer>>,
+) {
+    if !latest.is_changed() {
+        return;
+    }
+    let Some(snapshot) = &latest.0 else {
+        return;
+    };
+
+    // Entities are matched up with the snapshot by index and moved in place,
+    // only spawning or despawning when the number of shapes changes
+    let mut shapes_shown = 0;
+    for mut shown in &mut shown_shapes {
+        let Some(shape) = snapshot.shapes.get(shown.shown.0) else {
+            commands.entity(shown.entity).despawn();
+            continue;
+        };
+        let (mesh, material, transform) = assets.shape_parts(&mut meshes, shape);
+        shown.mesh.set_if_neq(mesh);
+        if shown.material.0 != material.0 {
+            *shown.material = material;
+        }
+        shown.transform.set_if_neq(transform);
+        shapes_shown += 1;
+    }
+    for (index, shape) in snapshot.shapes.iter().enumerate().skip(shapes_shown) {
+        let (mesh, material, transform) = assets.shape_parts(&mut meshes, shape);
+        commands.spawn((mesh, material, transform, ShownShape(index)));
+    }
+}
+
+fn snapshot_players_render_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    client: Res<NetClient>,
+    latest: Res<LatestSnapshot>,
+    mut shown_players: Query<
+        (Entity, &mut ShownPlayer, &mut Sprite, &mut Transform),
+        Without<ShownShape>,
+    >,
+) {
+    if !latest.is_changed() {
+        return;
+    }
+    let Some(snapshot) = &latest.0 else {
+        return;
+    };
+
+    let mut players_shown = 0;
+    for (entity, mut shown, mut sprite, mut transform) in &mut shown_players {
+        let Some(player) = snapshot.players.get(shown.index) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if shown.role != player.role {
+            shown.role = player.role;
+            sprite.image = asset_server.load(icon_for_role(&player.role));
+            if player.role == client.role {
+                commands.entity(entity).insert(HumanPlayer {
+                    index: 0,
+                    role: player.role,
+                });
+            } else {
+                commands.entity(entity).remove::<HumanPlayer>();
+            }
+        }
+        sprite.color = player_color(player);
+        transform.set_if_neq(Transform::from_translation(
+            player.position.extend(LAYER_PLAYER),
+        ));
+        players_shown += 1;
+    }
+    for (index, player) in snapshot.players.iter().enumerate().skip(players_shown) {
+        let mut entity = commands.spawn((
+            Sprite {
+                custom_size: Some(Vec2::splat(PLAYER_RADIUS * 2.)),
+                image: asset_server.load(icon_for_role(&player.role)),
+                color: player_color(player),
+                ..default()
+            },
+            Transform::from_translation(player.position.extend(LAYER_PLAYER)),
+            ShownPlayer {
+                index,
+                role: player.role,
+            },
+        ));
+        // Lets the follow camera and gamepad aiming find us
+        if player.role == client.role {
+            entity.insert(HumanPlayer {
+                index: 0,
+                role: player.role,
+            });
+        }
+    }
+}
+
+fn player_color(player: &PlayerState) -> Color {
+    if player.downed {
+        Color::srgb(0.4, 0.4, 0.4)
+    } else {
+        Color::WHITE
+    }
+}
+
+/// Frees up the role right away instead of waiting for the host to time us out
+fn client_leave_system(
+    client: Res<NetClient>,
+    mut close_requests: EventReader<WindowCloseRequested>,
+) {
+    if close_requests.read().count() > 0 {
+        send(&client.socket, client.host, &ClientMessage::Leave);
+    }
+}
+
+fn client_status_system(
+    client: Res<NetClient>,
+    latest: Res<LatestSnapshot>,
+    mut texts: Query<&mut Text2d, With<ClientStatusText>>,
+) {
+    let status = match &client.status {
+        ClientStatus::Joining => format!("Joining {} as {:?}...", client.host, client.role),
+        ClientStatus::Rejected(reason) => format!("Could not join: {}", reason),
+        ClientStatus::Playing => match &latest.0 {
+            None => format!("Joined as {:?}, waiting for the host to start", client.role),
+            Some(snapshot) => {
+                let hp = snapshot
+                    .players
+                    .iter()
+                    .find(|player| player.role == client.role)
+                    .map_or(0., |player| player.hp);
+                format!(
+                    "{:?} {:.0}s | {:?} {:.0} hp",
+                    snapshot.game_state, snapshot.time_elapsed, client.role, hp
+                )
+            }
+        },
+    };
+
+    for mut text in &mut texts {
+        if text.0 != status {
+            text.0 = status.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Serialize + DeserializeOwned + std::fmt::Debug>(message: &T) {
+        let bytes = encode(message).unwrap();
+        let decoded: T = decode(&bytes).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", message));
+    }
+
+    fn busy_snapshot(shapes: usize) -> Snapshot {
+        Snapshot {
+            sequence: 1234,
+            game_state: GameState::Jormag,
+            time_elapsed: 56.7,
+            players: AiRole::ALL
+                .into_iter()
+                .map(|role| PlayerState {
+                    role,
+                    position: Vec2::new(100., -200.),
+                    hp: 87.5,
+                    downed: role == AiRole::Dps2,
+                })
+                .collect(),
+            shapes: (0..shapes)
+                .map(|i| ShapeState {
+                    kind: ShapeKind::Aoe,
+                    position: Vec2::new(i as f32, -(i as f32)),
+                    rotation: 1.5,
+                    radius: 80.,
+                    shape: Some(CollisionShape::Cone {
+                        radius: 80.,
+                        half_angle: 0.6,
+                    }),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let mut input = HumanInput {
+            cursor: Vec2::new(12., 34.),
+            stick: Vec2::new(0.5, -0.5),
+            ..default()
+        };
+        input.pressed.insert(InputAction::MoveUp);
+        input.just_pressed.insert(InputAction::Dodge);
+
+        round_trip(&ClientMessage::Join {
+            role: AiRole::Herald2,
+        });
+        round_trip(&ClientMessage::Input(input));
+        round_trip(&ClientMessage::Leave);
+        round_trip(&ServerMessage::Welcome { role: AiRole::Ham1 });
+        round_trip(&ServerMessage::Rejected(
+            "Ham1 is already taken".to_string(),
+        ));
+        round_trip(&ServerMessage::Snapshot(busy_snapshot(3)));
+    }
+
+    #[test]
+    fn busy_snapshot_fits_in_a_datagram() {
+        let message = ServerMessage::Snapshot(busy_snapshot(1000));
+        assert!(encode(&message).unwrap().len() < MAX_DATAGRAM);
+    }
+}
@@ -1,10 +1,17 @@
-use bevy::{prelude::*, sprite::Anchor, window::CursorMoved};
+use bevy::{prelude::*, sprite::Anchor};
 
 use std::collections::HashSet;
 use std::ops::{Add, Mul, Sub};
 use std::time::Duration;
 
+use crate::boons::*;
+use crate::camera::hud_layer;
+use crate::downed::*;
+use crate::fast_forward::FastForward;
+use crate::input::{HumanInputs, InputAction, InputBindings};
+use crate::ledger::damage_ledger_system;
 use crate::mobs::*;
+use crate::skills::*;
 use crate::ui::*;
 use crate::{ai::AiPlayer, ai::AiRole, aoes::soup_duration_system};
 use crate::{audio::setup_audio, damage_flash::*};
@@ -28,6 +35,10 @@ pub const PORTAL_RADIUS: f32 = 24.;
 pub const DODGE_DURATION_S: f32 = 0.75;
 pub const JUMP_DURATION_S: f32 = 0.75;
 
+/// Where the skill bar puts each skill of the player's kit, in order
+const SKILL_SLOT_XS: [f32; 3] = [-128., 128., 256.];
+const SKILL_ICON_SIZE: f32 = 128.;
+
 #[derive(Component)]
 pub struct RotatingSoup {
     pub radius: f32,
@@ -98,59 +109,73 @@ fn effect_forced_march_system(
     }
 }
 
+/// Slows down or speeds up everything that ticks from `Res<Time>`
+pub fn game_speed_system(game: Res<Game>, mut time: ResMut<Time<Virtual>>) {
+    if time.relative_speed() != game.game_speed {
+        time.set_relative_speed(game.game_speed);
+    }
+}
+
 fn game_player_time_system(
     mut game: ResMut<Game>,
     time: Res<Time>,
-    mut players: Query<&mut Player>,
+    mut players: Query<(&mut Player, Has<Downed>)>,
 ) {
     game.time_elapsed.tick(time.delta());
 
-    for mut player in &mut players {
-        player.shoot_cooldown.tick(time.delta());
+    for (mut player, downed) in &mut players {
+        let shoot_delta = if player.boons.has(Boon::Quickness) {
+            time.delta().mul_f32(QUICKNESS_COOLDOWN_RATE)
+        } else {
+            time.delta()
+        };
+        let skill_delta = if player.boons.has(Boon::Alacrity) {
+            time.delta().mul_f32(ALACRITY_COOLDOWN_RATE)
+        } else {
+            time.delta()
+        };
+        player.shoot_cooldown.tick(shoot_delta);
         player.dodge_cooldown.tick(time.delta());
-        player.pull_cooldown.tick(time.delta());
-        player.blink_cooldown.tick(time.delta());
-        player.portal_cooldown.tick(time.delta());
-        player.jump_cooldown.tick(time.delta());
+        player.pull_cooldown.tick(skill_delta);
+        player.blink_cooldown.tick(skill_delta);
+        player.portal_cooldown.tick(skill_delta);
+        player.jump_cooldown.tick(skill_delta);
+        player.utility_cooldown.tick(skill_delta);
         player.invuln.tick(time.delta());
         player.jump.tick(time.delta());
-        player.heal(time.delta_secs() * PLAYER_REGEN);
-    }
-}
-
-fn game_player_damage_system(mut game: ResMut<Game>, players: Query<&Player>) {
-    let mut player_damage_taken = 0.;
-
-    for player in &players {
-        player_damage_taken += player.damage_taken;
+        player.boons.tick(time.delta_secs());
+        if !downed {
+            player.heal(time.delta_secs() * PLAYER_REGEN);
+        }
     }
-
-    game.player_damage_taken = f32::max(game.player_damage_taken, player_damage_taken);
 }
 
 fn handle_mouse_events_system(
     game: Res<Game>,
     asset_server: Res<AssetServer>,
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    inputs: Res<HumanInputs>,
     mut commands: Commands,
-    mut cursor_moved_events: EventReader<CursorMoved>,
-    mut players: Query<(Entity, &Transform, &mut Player), (Without<CursorMark>, Without<AiPlayer>)>,
-    mut cursors: Query<&mut Transform, With<CursorMark>>,
+    mut players: Query<
+        (Entity, &Transform, &mut Player, &SkillKit, &HumanPlayer),
+        (Without<CursorMark>, Without<Downed>),
+    >,
+    mut cursors: Query<(&mut Transform, &CursorMark)>,
 ) {
+    for (mut cursor, cursor_mark) in &mut cursors {
+        cursor.translation = inputs.slot(cursor_mark.0).cursor.extend(LAYER_CURSOR);
+    }
+
     let base_bullet_damage = if game.ai_enabled {
         BULLET_DAMAGE * 2.
     } else {
         BULLET_DAMAGE * 10.
     };
-    for (entity_player, transform_player, mut player) in &mut players {
+    for (entity_player, transform_player, mut player, kit, human_player) in &mut players {
+        let input = inputs.get(human_player);
+        let cursor_loc = input.cursor.extend(LAYER_CURSOR);
         let player_loc = transform_player.translation;
-        if player.shoot_cooldown.finished()
-            && (mouse_button_input.pressed(MouseButton::Left)
-                || keyboard_input.pressed(KeyCode::Digit1))
-        {
-            let cursor = cursors.single();
-            let mut vel = cursor.translation.sub(player_loc);
+        if player.shoot_cooldown.finished() && input.pressed(InputAction::Shoot) {
+            let mut vel = cursor_loc.sub(player_loc);
             vel.z = 0.;
             vel = vel.clamp_length(BULLET_SPEED, BULLET_SPEED);
 
@@ -165,7 +190,7 @@ fn handle_mouse_events_system(
                 Bullet {
                     age: 0.,
                     firer: entity_player,
-                    base_damage: base_bullet_damage,
+                    base_damage: base_bullet_damage * kit.bullet_damage_multiplier,
                 },
                 HasHit(HashSet::new()),
                 PhaseEntity,
@@ -181,37 +206,39 @@ fn handle_mouse_events_system(
             player.shoot_cooldown.reset();
         }
     }
-
-    for event in cursor_moved_events.read() {
-        let mut cursor = cursors.single_mut();
-        cursor.translation.x = event.position.x - WIDTH / 2.;
-        cursor.translation.y = HEIGHT / 2. - event.position.y;
-    }
 }
 
 fn handle_spellcasts_system(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    inputs: Res<HumanInputs>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut players: Query<(Entity, &Transform, &mut Player), (Without<CursorMark>, Without<AiPlayer>)>,
+    mut players: Query<
+        (Entity, &Transform, &mut Player, &SkillKit, &HumanPlayer),
+        (
+            Without<CursorMark>,
+            Without<Downed>,
+            Without<EffectForcedMarch>,
+        ),
+    >,
     portal_entries: Query<(&Transform, &PortalEntry)>,
     portal_exits: Query<(&Transform, &PortalExit)>,
-    cursors: Query<&Transform, With<CursorMark>>,
     crabs: Query<(Entity, &Transform, &MobCrab)>,
 ) {
-    let cursor_loc = cursors.single().translation;
-
-    for (entity_player, transform_player, mut player) in &mut players {
+    for (entity_player, transform_player, mut player, kit, human_player) in &mut players {
+        let input = inputs.get(human_player);
+        let cursor_loc = input.cursor.extend(LAYER_CURSOR);
         let player_loc = transform_player.translation;
 
-        if player.jump_cooldown.finished() && keyboard_input.pressed(KeyCode::Space) {
+        player.reviving = input.pressed(InputAction::Revive);
+
+        if player.jump_cooldown.finished() && input.pressed(InputAction::Jump) {
             player.jump = Timer::from_seconds(JUMP_DURATION_S, TimerMode::Once);
             player.jump_cooldown.reset();
 
             play_sfx(&mut commands, &asset_server, Sfx::Jump, SfxSource::Player);
         }
 
-        if player.dodge_cooldown.finished() && keyboard_input.pressed(KeyCode::KeyV) {
+        if player.dodge_cooldown.finished() && input.pressed(InputAction::Dodge) {
             let dodge_range = 300. * GAME_TO_PX;
             let dodge_speed = dodge_range / 0.75;
             let diff = cursor_loc
@@ -228,7 +255,10 @@ fn handle_spellcasts_system(
             player.dodge_cooldown.reset();
         }
 
-        if player.blink_cooldown.finished() && keyboard_input.pressed(KeyCode::KeyE) {
+        if kit.has(Skill::Blink)
+            && player.blink_cooldown.finished()
+            && input.pressed(InputAction::Blink)
+        {
             let blink_range = 1200.0 * GAME_TO_PX;
             let blink_speed = blink_range / 0.1;
             let mut diff = cursor_loc.sub(player_loc);
@@ -247,7 +277,10 @@ fn handle_spellcasts_system(
             play_sfx(&mut commands, &asset_server, Sfx::Blink, SfxSource::Player);
         }
 
-        if player.pull_cooldown.finished() && keyboard_input.pressed(KeyCode::Digit4) {
+        if kit.has(Skill::Pull)
+            && player.pull_cooldown.finished()
+            && input.pressed(InputAction::Pull)
+        {
             let pull_loc = cursor_loc;
             let pull_range = 600.0 * GAME_TO_PX;
             let pull_speed = pull_range / 0.3;
@@ -272,7 +305,10 @@ fn handle_spellcasts_system(
             play_sfx(&mut commands, &asset_server, Sfx::Pull, SfxSource::Player);
         }
 
-        if player.portal_cooldown.finished() && keyboard_input.just_pressed(KeyCode::KeyR) {
+        if kit.has(Skill::Portal)
+            && player.portal_cooldown.finished()
+            && input.just_pressed(InputAction::Portal)
+        {
             let portal_loc = player_loc;
 
             if portal_entries.is_empty() {
@@ -326,7 +362,7 @@ fn handle_spellcasts_system(
             }
         }
 
-        if keyboard_input.just_pressed(KeyCode::KeyF)
+        if input.just_pressed(InputAction::TakePortal)
             && !portal_entries.is_empty()
             && !portal_exits.is_empty()
         {
@@ -410,16 +446,23 @@ fn portal_despawn_system(
 
 fn handle_keyboard_system(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
     menu_state: Res<State<MenuState>>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    let start_pressed = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::Start));
+    if keyboard_input.just_pressed(KeyCode::Escape) || start_pressed {
         match menu_state.get() {
             MenuState::Paused | MenuState::PausedShowHint => {
                 next_menu_state.set(MenuState::Unpaused);
             }
-            MenuState::StartMenu | MenuState::Failure | MenuState::Success | MenuState::Loading => {
-            }
+            MenuState::StartMenu
+            | MenuState::Controls
+            | MenuState::Failure
+            | MenuState::Success
+            | MenuState::Loading => {}
             MenuState::Unpaused => {
                 next_menu_state.set(MenuState::Paused);
             }
@@ -440,28 +483,22 @@ fn move_rotating_soup_system(
 
 fn move_player_system(
     time: Res<Time>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    inputs: Res<HumanInputs>,
     mut transforms: Query<
-        (&mut Transform, &Player),
-        (Without<EffectForcedMarch>, Without<AiPlayer>),
+        (&mut Transform, &HumanPlayer, Has<Downed>),
+        (With<Player>, Without<EffectForcedMarch>),
     >,
 ) {
     // Much slower than actual movement
     let speed = 250.0 * GAME_TO_PX * time.delta_secs();
-    for (mut transform, _player) in &mut transforms {
-        let mut movement = Vec3::ZERO;
-        if keyboard_input.pressed(KeyCode::ArrowUp) || keyboard_input.pressed(KeyCode::KeyW) {
-            movement.y += speed;
-        }
-        if keyboard_input.pressed(KeyCode::ArrowDown) || keyboard_input.pressed(KeyCode::KeyS) {
-            movement.y -= speed;
-        }
-        if keyboard_input.pressed(KeyCode::ArrowLeft) || keyboard_input.pressed(KeyCode::KeyA) {
-            movement.x -= speed;
-        }
-        if keyboard_input.pressed(KeyCode::ArrowRight) || keyboard_input.pressed(KeyCode::KeyD) {
-            movement.x += speed;
-        }
+    for (mut transform, human_player, downed) in &mut transforms {
+        let input = inputs.get(human_player);
+        let speed = if downed {
+            speed * DOWNED_SPEED_SCALE
+        } else {
+            speed
+        };
+        let mut movement = input.movement().extend(0.) * speed;
         movement = movement.clamp_length(0., speed);
         transform.translation = transform.translation.add(movement);
     }
@@ -480,15 +517,40 @@ fn void_zone_growth_system(
     }
 }
 
-fn player_hp_check_system(players: Query<(Entity, &Player)>, mut commands: Commands) {
-    for (entity_player, player) in &players {
-        if player.get_hp() <= 0.1 {
-            commands.entity(entity_player).despawn_recursive();
+fn player_hp_check_system(
+    mut game: ResMut<Game>,
+    asset_server: Res<AssetServer>,
+    mut players: Query<(Entity, &mut Player, Has<Downed>)>,
+    mut commands: Commands,
+    mut death_events: EventWriter<PlayerDeathEvent>,
+) {
+    let time = game.time_elapsed.elapsed_secs();
+    for (entity_player, mut player, downed) in &mut players {
+        if player.get_hp() > 0.1 {
+            continue;
+        }
+
+        if !downed {
+            down_player(&mut commands, &asset_server, entity_player, &mut player);
+            continue;
         }
+
+        // The player won't be around for damage_ledger_system to see the killing blow
+        game.damage_ledger.record_hits(time, &mut player);
+        death_events.send(PlayerDeathEvent {
+            player: entity_player,
+            reason: player.last_damage_reason,
+            damage_taken: player.damage_taken,
+        });
+        commands.entity(entity_player).despawn_recursive();
     }
 }
 
-fn player_count_system(players: Query<&Player>, mut next_menu_state: ResMut<NextState<MenuState>>) {
+fn player_count_system(
+    players: Query<&Player, Without<Downed>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    // Nobody is left standing to revive anyone
     if players.is_empty() {
         next_menu_state.set(MenuState::Failure);
     }
@@ -564,66 +626,60 @@ fn enemies_hp_check_system(mut commands: Commands, enemies: Query<(Entity, &Hp),
 }
 
 pub fn add_update_phase_set(app: &mut App) {
+    // Everything here shares the same players, bullets and game state, so it
+    // runs in a fixed order for replays to line up frame for frame
     app.add_systems(
         Update,
         (
-            handle_mouse_events_system,
-            handle_spellcasts_system,
-            handle_keyboard_system,
-            velocities_system,
-            move_player_system,
-            move_rotating_soup_system,
-            effect_forced_march_system,
-        )
-            .in_set(PhaseSet::UpdatePhase),
-    );
-
-    app.add_systems(
-        Update,
-        (
-            collisions_players_edge_system,
-            collisions_players_echo_system,
-            collisions_bullets_enemies_system,
-            collisions_players_soups_system,
-            collisions_players_enemy_bullets_system,
+            (
+                handle_mouse_events_system,
+                handle_spellcasts_system,
+                utility_skill_system,
+                handle_keyboard_system,
+                velocities_system,
+                move_player_system,
+                move_rotating_soup_system,
+                effect_forced_march_system,
+            )
+                .chain(),
+            (
+                collisions_players_edge_system,
+                collisions_players_echo_system,
+                collisions_bullets_enemies_system,
+                collisions_players_soups_system,
+                collisions_players_enemy_bullets_system,
+            )
+                .chain(),
+            (
+                bullet_age_system,
+                player_text_system,
+                enemies_hp_check_system,
+                void_zone_growth_system,
+                player_hp_check_system,
+                soup_duration_system,
+                echo_grab_system,
+                echo_retarget_system,
+                scheduled_hint_system,
+                portal_despawn_system,
+                game_player_time_system,
+                boon_rotation_system,
+                damage_ledger_system,
+                player_count_system,
+                downed_bleed_out_system,
+                revive_system,
+            )
+                .chain(),
+            (
+                squad_frames_spawn_system,
+                squad_frames_status_system,
+                player_healthbar_update_gauge_system,
+                player_cooldown_update_gauge_system,
+                update_gauge_bars_system,
+                update_gauge_visibility_system,
+            )
+                .chain(),
+            (damage_flash_system, tint_untint_system).chain(),
         )
-            .in_set(PhaseSet::UpdatePhase),
-    );
-
-    app.add_systems(
-        Update,
-        (
-            bullet_age_system,
-            player_text_system,
-            enemies_hp_check_system,
-            void_zone_growth_system,
-            player_hp_check_system,
-            soup_duration_system,
-            echo_grab_system,
-            echo_retarget_system,
-            scheduled_hint_system,
-            portal_despawn_system,
-            game_player_time_system,
-            game_player_damage_system,
-            player_count_system,
-        )
-            .in_set(PhaseSet::UpdatePhase),
-    );
-
-    app.add_systems(
-        Update,
-        (
-            player_healthbar_update_gauge_system,
-            player_cooldown_update_gauge_system,
-            update_gauge_bars_system,
-            update_gauge_visibility_system,
-        )
-            .in_set(PhaseSet::UpdatePhase),
-    );
-
-    app.add_systems(
-        Update,
-        (damage_flash_system, tint_untint_system)
             .chain()
             .in_set(PhaseSet::UpdatePhase),
     );
@@ -644,7 +700,7 @@ fn icon_for_player_role(role: &AiRole) -> &'static str {
     }
 }
 
-fn icon_for_role(role: &AiRole) -> &'static str {
+pub fn icon_for_role(role: &AiRole) -> &'static str {
     match role {
         AiRole::Virt1 => "virt1.png",
         AiRole::Virt2 => "virt2.png",
@@ -659,61 +715,98 @@ fn icon_for_role(role: &AiRole) -> &'static str {
     }
 }
 
+/// Spawns human `index` playing `role` at their spot in the starting line
+pub fn spawn_human_player(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    index: usize,
+    role: AiRole,
+) {
+    let kit = SkillKit::for_role(&role);
+    let mut player = Player::new(format!("human {}", role.to_string()));
+    kit.equip(&mut player);
+    commands.spawn((
+        Sprite {
+            custom_size: Some(Vec2::new(PLAYER_RADIUS * 2., PLAYER_RADIUS * 2.)),
+            image: asset_server.load(icon_for_player_role(&role)),
+            ..default()
+        },
+        Transform::from_xyz(index as f32 * PLAYER_RADIUS * 3., 200., LAYER_PLAYER),
+        player,
+        kit,
+        HumanPlayer { index, role },
+    ));
+}
+
 pub fn setup_phase(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut game: ResMut<Game>,
     state: Res<State<GameState>>,
-    mut players: Query<(&mut Player, Option<&AiPlayer>)>,
+    mut players: Query<(Entity, &mut Player, Option<&AiPlayer>)>,
+    downed_players: Query<&Children, With<Downed>>,
+    downed_markers: Query<(), With<DownedMarker>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     phase_theme: Query<&AudioSink, With<AudioPhaseTheme>>,
+    bindings: Res<InputBindings>,
 ) {
     setup_hints(&mut commands, &game, state);
 
+    // Nobody starts a phase downed, even in a continuous run
+    for (entity_player, mut player, _) in &mut players {
+        let Ok(children) = downed_players.get(entity_player) else {
+            continue;
+        };
+        info!("{} was revived between phases", player.name);
+        player.set_hp(REVIVE_HP);
+        commands.entity(entity_player).remove::<Downed>();
+        for &child in children.iter() {
+            if downed_markers.contains(child) {
+                commands.entity(child).despawn_recursive();
+            }
+        }
+    }
+
     // Reset all cooldowns and invuln timings
     if !game.continuous {
         game.time_elapsed.reset();
-        game.player_damage_taken = 0.;
-        for (mut player, _) in &mut players {
+        game.damage_ledger.clear();
+        for (_, mut player, _) in &mut players {
             // Reset player hp
             player.heal(10000.);
             player.dodge_cooldown.tick(Duration::from_secs_f32(1000.));
             player.blink_cooldown.tick(Duration::from_secs_f32(1000.));
             player.portal_cooldown.tick(Duration::from_secs_f32(1000.));
             player.pull_cooldown.tick(Duration::from_secs_f32(1000.));
+            player.utility_cooldown.tick(Duration::from_secs_f32(1000.));
             player.invuln.tick(Duration::from_secs_f32(1000.));
             player.jump.tick(Duration::from_secs_f32(1000.));
         }
+
+        if game.start_time > 0. {
+            commands.insert_resource(FastForward(game.start_time));
+        }
     }
 
     if players.is_empty() {
         game.time_elapsed.reset();
 
         if game.ai_enabled {
+            let human_roles = game.human_player_roles();
             let mut x: f32 = 0.;
-            for role in [
-                AiRole::Virt1,
-                AiRole::Virt2,
-                AiRole::Herald1,
-                AiRole::Herald2,
-                AiRole::Ham1,
-                AiRole::Ham2,
-                AiRole::Dps1,
-                AiRole::Dps2,
-                AiRole::Dps3,
-                AiRole::Dps4,
-            ] {
-                if let Some(player_role) = game.player_role {
-                    if role == player_role {
-                        // Player is stepping in for whoever this is
-                        continue;
-                    }
+            for role in AiRole::ALL {
+                if human_roles
+                    .iter()
+                    .any(|(_, human_role)| *human_role == role)
+                {
+                    // A human is stepping in for whoever this is
+                    continue;
                 }
 
                 let bar_height = 4.;
 
-                commands
+                let entity_ai = commands
                     .spawn((
                         Sprite {
                             custom_size: Some(Vec2::new(PLAYER_RADIUS * 2., PLAYER_RADIUS * 2.)),
@@ -779,21 +872,7 @@ pub fn setup_phase(
                                 ));
                             });
 
-                        let mut cooldowns = vec![PlayerCooldown::Dodge, PlayerCooldown::Jump];
-
-                        match role {
-                            AiRole::Ham1 | AiRole::Ham2 | AiRole::Virt1 | AiRole::Virt2 => {
-                                cooldowns.push(PlayerCooldown::Blink)
-                            }
-                            AiRole::Herald1
-                            | AiRole::Herald2
-                            | AiRole::Dps1
-                            | AiRole::Dps2
-                            | AiRole::Dps3
-                            | AiRole::Dps4 => {}
-                        }
-
-                        let cooldown_bars: Vec<PlayerCooldownBar> = cooldowns
+                        let cooldown_bars: Vec<PlayerCooldownBar> = role_cooldowns(&role)
                             .into_iter()
                             .map(|cooldown| PlayerCooldownBar {
                                 player: parent.parent_entity(),
@@ -846,25 +925,21 @@ pub fn setup_phase(
                                     ));
                                 });
                         }
-                    });
+                    })
+                    .id();
+                if let Some(rotation) = BoonRotation::for_role(&role) {
+                    commands.entity(entity_ai).insert(rotation);
+                }
                 x += 1.;
             }
         }
 
-        if let Some(player_role) = game.player_role {
-            commands.spawn((
-                Sprite {
-                    custom_size: Some(Vec2::new(PLAYER_RADIUS * 2., PLAYER_RADIUS * 2.)),
-                    image: asset_server.load(icon_for_player_role(&player_role)),
-                    ..default()
-                },
-                Transform::from_xyz(0., 200., LAYER_PLAYER),
-                Player::new(format!("human {}", player_role.to_string())),
-            ));
+        for (index, player_role) in game.human_player_roles() {
+            spawn_human_player(&mut commands, &asset_server, index, player_role);
         }
     }
 
-    for (mut player, ai_player) in &mut players {
+    for (_, mut player, ai_player) in &mut players {
         if ai_player.is_none() {
             continue;
         }
@@ -872,6 +947,22 @@ pub fn setup_phase(
         player.heal(100.);
     }
 
+    if let Some(label) = game.game_speed_label() {
+        commands.spawn((
+            Text2d::new(format!("Speed: {}", label)),
+            TextFont {
+                font: asset_server.load("trebuchet_ms.ttf"),
+                font_size: 24.,
+                ..default()
+            },
+            TextColor(Color::srgb(1., 0.9, 0.2)),
+            Anchor::BottomLeft,
+            Transform::from_xyz(-WIDTH / 2. + 10., -HEIGHT / 2. + 10., LAYER_TEXT),
+            hud_layer(),
+            PhaseEntity,
+        ));
+    }
+
     if game.echo_enabled {
         commands.spawn((
             Sprite {
@@ -898,16 +989,22 @@ pub fn setup_phase(
         )))
         .insert(PhaseEntity);
 
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.9, 0., 0.),
-            custom_size: Some(Vec2::new(4., 4.)),
-            ..default()
-        },
-        Transform::from_xyz(0., 0., LAYER_CURSOR),
-        CursorMark,
-        PhaseEntity,
-    ));
+    for index in 0..LOCAL_PLAYERS {
+        commands.spawn((
+            Sprite {
+                color: if index == 0 {
+                    Color::srgb(0.9, 0., 0.)
+                } else {
+                    Color::srgb(0., 0.6, 0.9)
+                },
+                custom_size: Some(Vec2::new(4., 4.)),
+                ..default()
+            },
+            Transform::from_xyz(0., 0., LAYER_CURSOR),
+            CursorMark(index),
+            PhaseEntity,
+        ));
+    }
 
     commands.spawn((
         Sprite {
@@ -918,8 +1015,31 @@ pub fn setup_phase(
         PhaseEntity,
     ));
 
-    if game.player_role.is_some() {
-        setup_player_ui(&mut commands, &asset_server, &mut meshes, &mut materials);
+    let local_roles = game.local_player_roles();
+    for &(index, player_role) in &local_roles {
+        // Side by side and a bit smaller when two people share the screen
+        let layout = if local_roles.len() > 1 {
+            HudLayout {
+                human_player: index,
+                x: if index == 0 { -WIDTH / 4. } else { WIDTH / 4. },
+                scale: 0.6,
+            }
+        } else {
+            HudLayout {
+                human_player: index,
+                x: 0.,
+                scale: 1.,
+            }
+        };
+        setup_player_ui(
+            &mut commands,
+            &asset_server,
+            &mut meshes,
+            &mut materials,
+            &bindings,
+            &SkillKit::for_role(&player_role),
+            &layout,
+        );
     }
 
     if phase_theme.is_empty() {
@@ -935,11 +1055,30 @@ pub fn setup_phase(
     }
 }
 
+/// Where one local player's skill bar sits along the bottom of the screen
+struct HudLayout {
+    human_player: usize,
+    x: f32,
+    scale: f32,
+}
+
+impl HudLayout {
+    /// Places something `x` from the middle of this skill bar and `y` up
+    /// from the bottom of the screen
+    fn transform(&self, x: f32, y: f32, z: f32) -> Transform {
+        Transform::from_xyz(self.x + x * self.scale, -HEIGHT / 2. + y * self.scale, z)
+            .with_scale(Vec3::splat(self.scale))
+    }
+}
+
 fn setup_player_ui(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
+    bindings: &InputBindings,
+    kit: &SkillKit,
+    layout: &HudLayout,
 ) {
     let text_font = TextFont {
         font: asset_server.load("trebuchet_ms.ttf"),
@@ -960,18 +1099,21 @@ fn setup_player_ui(
         text_color,
         TextLayout::new_with_justify(JustifyText::Center),
         Anchor::Center,
-        Transform::from_xyz(0., -HEIGHT / 2. + 55., LAYER_TEXT),
+        layout.transform(0., 55., LAYER_TEXT),
         TextDisplay {
             value: TextValue::Hp,
             sprite: None,
+            human_player: layout.human_player,
         },
+        hud_layer(),
         PhaseEntity,
     ));
 
     commands.spawn((
         Mesh2d(meshes.add(Circle::new(50.))),
         MeshMaterial2d(materials.add(ColorMaterial::from(Color::srgb(0.6, 0.1, 0.1)))),
-        Transform::from_xyz(0., -HEIGHT / 2. + 55., LAYER_UI),
+        layout.transform(0., 55., LAYER_UI),
+        hud_layer(),
         PhaseEntity,
     ));
 
@@ -981,11 +1123,13 @@ fn setup_player_ui(
         TextColor(Color::srgb(0.7, 0.7, 0.1)),
         TextLayout::new_with_justify(JustifyText::Center),
         Anchor::Center,
-        Transform::from_xyz(0., -HEIGHT / 2. + 155., LAYER_TEXT),
+        layout.transform(0., 155., LAYER_TEXT),
         TextDisplay {
             value: TextValue::CooldownDodge,
             sprite: None,
+            human_player: layout.human_player,
         },
+        hud_layer(),
         PhaseEntity,
     ));
 
@@ -995,118 +1139,70 @@ fn setup_player_ui(
         TextColor(Color::srgb(0.1, 0.7, 0.7)),
         TextLayout::new_with_justify(JustifyText::Right),
         Anchor::CenterRight,
-        Transform::from_xyz(-90., -HEIGHT / 2. + 155., LAYER_TEXT),
+        layout.transform(-90., 155., LAYER_TEXT),
         TextDisplay {
             value: TextValue::StatusJump,
             sprite: None,
+            human_player: layout.human_player,
         },
+        hud_layer(),
         PhaseEntity,
     ));
 
-    let sprite_pull = commands
-        .spawn((
-            Sprite {
-                image: asset_server.load("pull.png"),
-                ..default()
-            },
-            Transform::from_xyz(-128., -HEIGHT / 2. + 55., LAYER_UI),
-            PhaseEntity,
-        ))
-        .id();
-
-    commands.spawn((
-        Text2d::new("0"),
-        text_font.clone(),
-        text_color,
-        TextLayout::new_with_justify(JustifyText::Center),
-        Anchor::Center,
-        Transform::from_xyz(-128., -HEIGHT / 2. + 55., LAYER_TEXT),
-        TextDisplay {
-            value: TextValue::CooldownPull,
-            sprite: Some(sprite_pull),
-        },
-        PhaseEntity,
-    ));
+    for (skill, x) in kit.skills.iter().zip(SKILL_SLOT_XS) {
+        let sprite_skill = commands
+            .spawn((
+                Sprite {
+                    image: asset_server.load(skill.icon()),
+                    custom_size: Some(Vec2::splat(SKILL_ICON_SIZE)),
+                    ..default()
+                },
+                layout.transform(x, 55., LAYER_UI),
+                hud_layer(),
+                PhaseEntity,
+            ))
+            .id();
 
-    commands.spawn((
-        Text2d::new("4"),
-        text_font_binding.clone(),
-        text_color_binding,
-        TextLayout::new_with_justify(JustifyText::Center),
-        Anchor::Center,
-        Transform::from_xyz(-128., -HEIGHT / 2. + binding_y, LAYER_TEXT),
-        PhaseEntity,
-    ));
+        let value = match skill {
+            Skill::Pull => TextValue::CooldownPull,
+            Skill::Blink => TextValue::CooldownBlink,
+            Skill::Portal => TextValue::CooldownPortal,
+            Skill::GroupHeal | Skill::Facet => TextValue::CooldownUtility,
+        };
 
-    let sprite_blink = commands
-        .spawn((
-            Sprite {
-                image: asset_server.load("blink.png"),
-                ..default()
+        commands.spawn((
+            Text2d::new(""),
+            text_font.clone(),
+            text_color,
+            TextLayout::new_with_justify(JustifyText::Center),
+            Anchor::Center,
+            layout.transform(x, 55., LAYER_TEXT),
+            TextDisplay {
+                value,
+                sprite: Some(sprite_skill),
+                human_player: layout.human_player,
             },
-            Transform::from_xyz(128., -HEIGHT / 2. + 55., LAYER_UI),
+            hud_layer(),
             PhaseEntity,
-        ))
-        .id();
-
-    commands.spawn((
-        Text2d::new(""),
-        text_font.clone(),
-        text_color,
-        TextLayout::new_with_justify(JustifyText::Center),
-        Anchor::Center,
-        Transform::from_xyz(128., -HEIGHT / 2. + 55., LAYER_TEXT),
-        TextDisplay {
-            value: TextValue::CooldownBlink,
-            sprite: Some(sprite_blink),
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        Text2d::new("E"),
-        text_font_binding.clone(),
-        text_color_binding,
-        TextLayout::new_with_justify(JustifyText::Center),
-        Anchor::Center,
-        Transform::from_xyz(128., -HEIGHT / 2. + binding_y, LAYER_TEXT),
-        PhaseEntity,
-    ));
+        ));
 
-    let sprite_portal = commands
-        .spawn((
-            Sprite {
-                image: asset_server.load("portal.png"),
-                ..default()
-            },
-            Transform::from_xyz(256., -HEIGHT / 2. + 55., LAYER_UI),
+        // The second player is always on a gamepad
+        let binding_label = if layout.human_player == 0 {
+            bindings.label(skill.action())
+        } else {
+            bindings.gamepad_label(skill.action())
+        };
+        commands.spawn((
+            Text2d::new(binding_label),
+            text_font_binding.clone(),
+            text_color_binding,
+            TextLayout::new_with_justify(JustifyText::Center),
+            Anchor::Center,
+            layout.transform(x, binding_y, LAYER_TEXT),
+            hud_layer(),
             PhaseEntity,
-        ))
-        .id();
-
-    commands.spawn((
-        Text2d::new(""),
-        text_font.clone(),
-        text_color,
-        TextLayout::new_with_justify(JustifyText::Center),
-        Anchor::Center,
-        Transform::from_xyz(256., -HEIGHT / 2. + 55., LAYER_TEXT),
-        TextDisplay {
-            value: TextValue::CooldownPortal,
-            sprite: Some(sprite_portal),
-        },
-        PhaseEntity,
-    ));
-
-    commands.spawn((
-        Text2d::new("R"),
-        text_font_binding.clone(),
-        text_color_binding,
-        TextLayout::new_with_justify(JustifyText::Center),
-        Anchor::Center,
-        Transform::from_xyz(256., -HEIGHT / 2. + binding_y, LAYER_TEXT),
-        PhaseEntity,
-    ));
+        ));
+    }
 }
 
 pub fn cleanup_phase(
@@ -3,6 +3,7 @@ use std::ops::{Add, Mul, Sub};
 
 use crate::aoes::*;
 use crate::collisions::*;
+use crate::ledger::DamageSource;
 use crate::mobs::*;
 use crate::orbs::*;
 use crate::phase::*;
@@ -74,6 +75,7 @@ pub fn add_update_purification_phase_set(app: &mut App) {
             void_zone_crab_system,
             player_ai_purification_phase_system,
         )
+            .chain()
             .in_set(PhaseSet::UpdatePurificationPhase),
     );
 }
@@ -117,6 +119,7 @@ pub fn setup_purification(
                 damage: 25.,
                 duration: None,
             },
+            DamageSource("void zone"),
             PhaseEntity,
         ));
     }
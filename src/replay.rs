@@ -0,0 +1,464 @@
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    time::{TimeSystem, TimeUpdateStrategy},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::ai::AiRole;
+use crate::game::*;
+use crate::input::*;
+use crate::roster::{PlayerHandOver, RosterChange, RosterChanges};
+
+pub const REPLAY_VERSION: u32 = 4;
+
+/// The parts of [`Game`] that change how a run plays out
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunSettings {
+    pub game_state: GameState,
+    pub continuous: bool,
+    pub echo_enabled: bool,
+    pub hints_enabled: bool,
+    pub ai_enabled: bool,
+    pub ai_bars_enabled: bool,
+    pub puddles_enabled: bool,
+    pub greens_enabled: bool,
+    pub unlimited_range_enabled: bool,
+    pub player_role: Option<AiRole>,
+    #[serde(default)]
+    pub second_player_role: Option<AiRole>,
+    #[serde(default)]
+    pub remote_player_roles: Vec<Option<AiRole>>,
+    #[serde(default)]
+    pub start_time: f32,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default = "default_game_speed")]
+    pub game_speed: f32,
+}
+
+fn default_game_speed() -> f32 {
+    1.
+}
+
+impl RunSettings {
+    pub fn from_game(game: &Game, game_state: GameState) -> RunSettings {
+        RunSettings {
+            game_state,
+            continuous: game.continuous,
+            echo_enabled: game.echo_enabled,
+            hints_enabled: game.hints_enabled,
+            ai_enabled: game.ai_enabled,
+            ai_bars_enabled: game.ai_bars_enabled,
+            puddles_enabled: game.puddles_enabled,
+            greens_enabled: game.greens_enabled,
+            unlimited_range_enabled: game.unlimited_range_enabled,
+            player_role: game.player_role,
+            second_player_role: game.second_player_role,
+            remote_player_roles: game.remote_player_roles.clone(),
+            start_time: game.start_time,
+            difficulty: game.difficulty,
+            game_speed: game.game_speed,
+        }
+    }
+
+    pub fn apply(&self, game: &mut Game) {
+        game.continuous = self.continuous;
+        game.echo_enabled = self.echo_enabled;
+        game.hints_enabled = self.hints_enabled;
+        game.ai_enabled = self.ai_enabled;
+        game.ai_bars_enabled = self.ai_bars_enabled;
+        game.puddles_enabled = self.puddles_enabled;
+        game.greens_enabled = self.greens_enabled;
+        game.unlimited_range_enabled = self.unlimited_range_enabled;
+        game.player_role = self.player_role;
+        game.second_player_role = self.second_player_role;
+        game.remote_player_roles = self.remote_player_roles.clone();
+        game.start_time = self.start_time;
+        game.difficulty = self.difficulty;
+        game.game_speed = closest_game_speed(self.game_speed);
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub delta_nanos: u64,
+    pub inputs: HumanInputs,
+    /// Online players who joined or left just before this frame
+    #[serde(default)]
+    pub roster_changes: Vec<RosterChange>,
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct Recording {
+    pub version: u32,
+    pub settings: RunSettings,
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    recording: Recording,
+    frame: usize,
+    started: bool,
+}
+
+impl ReplayPlayback {
+    pub fn new(recording: Recording) -> ReplayPlayback {
+        ReplayPlayback {
+            recording,
+            frame: 0,
+            started: false,
+        }
+    }
+
+    fn current_frame(&self) -> Option<&ReplayFrame> {
+        self.recording.frames.get(self.frame)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("could not read replay: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse replay: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("unsupported replay version {0}, expected {REPLAY_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("replay frame {frame} has {inputs} inputs, expected at least {LOCAL_PLAYERS}")]
+    MissingInputs { frame: usize, inputs: usize },
+}
+
+pub fn parse_recording(bytes: &[u8]) -> Result<Recording, ReplayError> {
+    let recording = ron::de::from_bytes::<Recording>(bytes)?;
+    if recording.version != REPLAY_VERSION {
+        return Err(ReplayError::UnsupportedVersion(recording.version));
+    }
+    for (frame, replay_frame) in recording.frames.iter().enumerate() {
+        let inputs = replay_frame.inputs.0.len();
+        if inputs < LOCAL_PLAYERS {
+            return Err(ReplayError::MissingInputs { frame, inputs });
+        }
+    }
+    Ok(recording)
+}
+
+/// Loads the replay passed with `--replay <path>`, if any
+#[cfg(not(target_arch = "wasm32"))]
+pub fn replay_from_args() -> Option<Result<ReplayPlayback, ReplayError>> {
+    let args: Vec<String> = std::env::args().collect();
+    let path_index = args.iter().position(|arg| arg == "--replay")? + 1;
+    let path = args.get(path_index)?;
+    Some(
+        std::fs::read(path)
+            .map_err(ReplayError::from)
+            .and_then(|bytes| parse_recording(&bytes))
+            .map(ReplayPlayback::new),
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_recording(recording: &Recording) {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let dir = std::path::Path::new("replays");
+    if let Err(err) = std::fs::create_dir_all(dir) {
+        warn!("could not create replay directory: {}", err);
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!(
+        "{:?}-{}.replay.ron",
+        recording.settings.game_state, timestamp
+    ));
+
+    let contents = match ron::to_string(recording) {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!("could not serialize replay: {}", err);
+            return;
+        }
+    };
+
+    match std::fs::write(&path, contents) {
+        Ok(_) => info!("saved replay to {}", path.display()),
+        Err(err) => warn!("could not save replay to {}: {}", path.display(), err),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_recording(_recording: &Recording) {}
+
+fn start_replay_system(
+    mut commands: Commands,
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut game: ResMut<Game>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+
+    if playback.started {
+        // Back at the menu after a replay was abandoned
+        commands.remove_resource::<ReplayPlayback>();
+        commands.insert_resource(TimeUpdateStrategy::Automatic);
+        return;
+    }
+
+    playback.started = true;
+    playback.recording.settings.apply(&mut game);
+    // Hints would pause the replay waiting for a click
+    game.hints_enabled = false;
+    next_game_state.set(playback.recording.settings.game_state);
+    next_menu_state.set(MenuState::Unpaused);
+}
+
+fn start_recording_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    game_state: Res<State<GameState>>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut roster_changes: ResMut<RosterChanges>,
+) {
+    // Anyone who joined at the menu is already in the run settings
+    roster_changes.0.clear();
+
+    if let Some(playback) = playback {
+        commands.insert_resource(GameRng::from_seed(playback.recording.seed));
+        return;
+    }
+
+    let seed: u64 = rand::random();
+    commands.insert_resource(GameRng::from_seed(seed));
+
+    if game.human_player_roles().is_empty() {
+        commands.remove_resource::<Recording>();
+        return;
+    }
+
+    commands.insert_resource(Recording {
+        version: REPLAY_VERSION,
+        settings: RunSettings::from_game(&game, *game_state.get()),
+        seed,
+        frames: vec![],
+    });
+}
+
+fn finish_recording_system(
+    mut commands: Commands,
+    playback: Option<Res<ReplayPlayback>>,
+    recording: Option<Res<Recording>>,
+) {
+    if playback.is_some() {
+        commands.remove_resource::<ReplayPlayback>();
+        commands.insert_resource(TimeUpdateStrategy::Automatic);
+        return;
+    }
+
+    if let Some(recording) = recording {
+        save_recording(&recording);
+        commands.remove_resource::<Recording>();
+    }
+}
+
+fn replay_time_system(
+    playback: Res<ReplayPlayback>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    if let Some(frame) = playback.current_frame() {
+        *time_update_strategy =
+            TimeUpdateStrategy::ManualDuration(Duration::from_nanos(frame.delta_nanos));
+    }
+}
+
+fn replay_input_system(playback: Res<ReplayPlayback>, mut inputs: ResMut<HumanInputs>) {
+    *inputs = playback
+        .current_frame()
+        .map(|frame| frame.inputs.clone())
+        .unwrap_or_default();
+    inputs.ensure_len(LOCAL_PLAYERS);
+}
+
+fn replay_roster_system(
+    playback: Res<ReplayPlayback>,
+    mut game: ResMut<Game>,
+    mut hand_over: PlayerHandOver,
+) {
+    let Some(frame) = playback.current_frame() else {
+        return;
+    };
+    for change in &frame.roster_changes {
+        hand_over.hand_over_players(&mut game, *change);
+    }
+}
+
+fn record_input_system(
+    real_time: Res<Time<Real>>,
+    inputs: Res<HumanInputs>,
+    mut roster_changes: ResMut<RosterChanges>,
+    recording: Option<ResMut<Recording>>,
+    playback: Option<ResMut<ReplayPlayback>>,
+) {
+    let roster_changes = std::mem::take(&mut roster_changes.0);
+
+    if let Some(mut playback) = playback {
+        playback.frame += 1;
+        return;
+    }
+
+    if let Some(mut recording) = recording {
+        recording.frames.push(ReplayFrame {
+            delta_nanos: real_time.delta().as_nanos() as u64,
+            inputs: inputs.clone(),
+            roster_changes,
+        });
+    }
+}
+
+pub fn add_replay_systems(app: &mut App) {
+    app.init_resource::<HumanInputs>()
+        .init_resource::<GameRng>()
+        .init_resource::<RosterChanges>()
+        .add_systems(
+            First,
+            replay_time_system
+                .before(TimeSystem)
+                .run_if(resource_exists::<ReplayPlayback>),
+        )
+        .add_systems(
+            PreUpdate,
+            (
+                (read_human_input_system, read_gamepad_input_system)
+                    .chain()
+                    .run_if(not(resource_exists::<ReplayPlayback>)),
+                (replay_input_system, replay_roster_system)
+                    .run_if(resource_exists::<ReplayPlayback>),
+            )
+                .after(InputSystem),
+        )
+        .add_systems(Update, record_input_system.in_set(PhaseSet::UpdatePhase))
+        .add_systems(OnEnter(MenuState::StartMenu), start_replay_system)
+        .add_systems(OnExit(GameState::Nothing), start_recording_system)
+        .add_systems(OnEnter(MenuState::Success), finish_recording_system)
+        .add_systems(OnEnter(MenuState::Failure), finish_recording_system);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{headless_app, simulation_game};
+
+    const RECORDED_FRAMES: usize = 600;
+
+    /// Sits at the start menu, where runs and replays both start from
+    fn test_app(game: Game, playback: Option<ReplayPlayback>) -> App {
+        let mut app = headless_app(game);
+        if let Some(playback) = playback {
+            app.insert_resource(playback);
+        }
+        app.world_mut()
+            .resource_mut::<NextState<MenuState>>()
+            .set(MenuState::StartMenu);
+        app.update();
+        app
+    }
+
+    /// Every player's name, position and hp, sorted by name
+    fn player_states(app: &mut App) -> Vec<(String, Vec3, f32)> {
+        let mut players = app.world_mut().query::<(&Player, &Transform)>();
+        let mut states: Vec<_> = players
+            .iter(app.world())
+            .map(|(player, transform)| {
+                (player.name.clone(), transform.translation, player.get_hp())
+            })
+            .collect();
+        states.sort_by(|a, b| a.0.cmp(&b.0));
+        states
+    }
+
+    #[test]
+    fn replay_matches_recorded_run() {
+        let game = Game {
+            player_role: Some(AiRole::Dps1),
+            ..simulation_game()
+        };
+        let mut app = test_app(game, None);
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Primordus);
+        app.world_mut()
+            .resource_mut::<NextState<MenuState>>()
+            .set(MenuState::Unpaused);
+
+        for frame in 0..RECORDED_FRAMES {
+            let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            match frame {
+                0 => keys.press(KeyCode::KeyD),
+                200 => {
+                    keys.release(KeyCode::KeyD);
+                    keys.press(KeyCode::KeyW);
+                }
+                400 => keys.release(KeyCode::KeyW),
+                _ => {}
+            }
+            app.update();
+        }
+
+        let recording = app.world().resource::<Recording>().clone();
+        assert_eq!(recording.frames.len(), RECORDED_FRAMES);
+        let recorded = player_states(&mut app);
+        assert!(!recorded.is_empty());
+
+        let mut app = test_app(simulation_game(), Some(ReplayPlayback::new(recording)));
+        let mut replayed = 0;
+        while app
+            .world()
+            .get_resource::<ReplayPlayback>()
+            .is_some_and(|playback| playback.frame < RECORDED_FRAMES)
+        {
+            app.update();
+            replayed += 1;
+            assert!(replayed < RECORDED_FRAMES * 2, "replay never finished");
+        }
+
+        assert_eq!(player_states(&mut app), recorded);
+    }
+
+    #[test]
+    fn parse_rejects_frames_missing_local_inputs() {
+        let mut recording = Recording {
+            version: REPLAY_VERSION,
+            settings: RunSettings::from_game(&simulation_game(), GameState::Primordus),
+            seed: 0,
+            frames: vec![
+                ReplayFrame {
+                    delta_nanos: 0,
+                    inputs: HumanInputs::default(),
+                    roster_changes: vec![],
+                };
+                2
+            ],
+        };
+        let bytes = ron::to_string(&recording).unwrap();
+        assert!(parse_recording(bytes.as_bytes()).is_ok());
+
+        recording.frames[1].inputs.0.truncate(1);
+        let bytes = ron::to_string(&recording).unwrap();
+        assert!(matches!(
+            parse_recording(bytes.as_bytes()),
+            Err(ReplayError::MissingInputs {
+                frame: 1,
+                inputs: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::ai::{AiPlayer, AiRole};
+use crate::boons::BoonRotation;
+use crate::game::{Game, HumanPlayer, Player, LOCAL_PLAYERS};
+use crate::phase::spawn_human_player;
+use crate::skills::SkillKit;
+
+/// A human joining or leaving the squad partway through a run
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum RosterChange {
+    Joined { index: usize, role: AiRole },
+    Left { index: usize },
+}
+
+impl RosterChange {
+    fn apply(&self, game: &mut Game) {
+        match *self {
+            RosterChange::Joined { index, role } => {
+                let Some(slot) = index.checked_sub(LOCAL_PLAYERS) else {
+                    return;
+                };
+                if game.remote_player_roles.len() <= slot {
+                    game.remote_player_roles.resize(slot + 1, None);
+                }
+                game.remote_player_roles[slot] = Some(role);
+            }
+            RosterChange::Left { index } => {
+                let slot = index.checked_sub(LOCAL_PLAYERS);
+                if let Some(role) = slot.and_then(|slot| game.remote_player_roles.get_mut(slot)) {
+                    *role = None;
+                }
+            }
+        }
+    }
+}
+
+/// Roster changes since the last frame was recorded, so replays hand players
+/// over on the same frame
+#[derive(Resource, Default)]
+pub struct RosterChanges(pub Vec<RosterChange>);
+
+/// Keeps the phase in progress in step with the roster. A new human takes over
+/// the AI playing their role and someone who left is handed back to the AI
+#[derive(SystemParam)]
+pub struct PlayerHandOver<'w, 's> {
+    commands: Commands<'w, 's>,
+    ai_players: Query<'w, 's, (Entity, &'static AiPlayer, &'static mut Player)>,
+    human_players: Query<'w, 's, (Entity, &'static HumanPlayer)>,
+    players: Query<'w, 's, (), With<Player>>,
+    roster_changes: ResMut<'w, RosterChanges>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+impl PlayerHandOver<'_, '_> {
+    pub fn hand_over_players(&mut self, game: &mut Game, change: RosterChange) {
+        change.apply(game);
+        self.roster_changes.0.push(change);
+
+        match change {
+            RosterChange::Left { index } => {
+                for (entity, human_player) in &self.human_players {
+                    if human_player.index != index {
+                        continue;
+                    }
+                    let role = human_player.role;
+                    let mut entity = self.commands.entity(entity);
+                    entity
+                        .remove::<(HumanPlayer, SkillKit)>()
+                        .insert(AiPlayer { role });
+                    if let Some(rotation) = BoonRotation::for_role(&role) {
+                        entity.insert(rotation);
+                    }
+                }
+            }
+            RosterChange::Joined { index, role } => {
+                let mut taken_over = false;
+                for (entity, ai_player, mut player) in &mut self.ai_players {
+                    if ai_player.role != role {
+                        continue;
+                    }
+                    let kit = SkillKit::for_role(&role);
+                    kit.equip(&mut player);
+                    self.commands
+                        .entity(entity)
+                        .remove::<(AiPlayer, BoonRotation)>()
+                        .insert((HumanPlayer { index, role }, kit));
+                    taken_over = true;
+                }
+                // Nobody to take over with the AI off or their role already
+                // dead. Before a phase starts, setup_phase spawns the roster
+                if !taken_over && !self.players.is_empty() {
+                    spawn_human_player(&mut self.commands, &self.asset_server, index, role);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_resource::<Game>()
+            .init_resource::<RosterChanges>();
+        app
+    }
+
+    #[test]
+    fn hand_over_players_swaps_ai_and_humans() {
+        let mut app = test_app();
+        let world = app.world_mut();
+        let joiner = world
+            .spawn((
+                Player::new("joiner".to_string()),
+                AiPlayer {
+                    role: AiRole::Herald2,
+                },
+            ))
+            .id();
+        let leaver = world
+            .spawn((
+                Player::new("leaver".to_string()),
+                HumanPlayer {
+                    index: LOCAL_PLAYERS,
+                    role: AiRole::Dps3,
+                },
+                SkillKit::for_role(&AiRole::Dps3),
+            ))
+            .id();
+        world.resource_mut::<Game>().remote_player_roles = vec![Some(AiRole::Dps3)];
+
+        let changes = [
+            RosterChange::Left {
+                index: LOCAL_PLAYERS,
+            },
+            RosterChange::Joined {
+                index: LOCAL_PLAYERS + 1,
+                role: AiRole::Herald2,
+            },
+        ];
+        world
+            .run_system_once(
+                move |mut game: ResMut<Game>, mut hand_over: PlayerHandOver| {
+                    for change in changes {
+                        hand_over.hand_over_players(&mut game, change);
+                    }
+                },
+            )
+            .unwrap();
+
+        let human_player = world.get::<HumanPlayer>(joiner).unwrap();
+        assert_eq!(human_player.index, LOCAL_PLAYERS + 1);
+        assert!(world.get::<AiPlayer>(joiner).is_none());
+        assert!(world.get::<SkillKit>(joiner).is_some());
+
+        assert_eq!(world.get::<AiPlayer>(leaver).unwrap().role, AiRole::Dps3);
+        assert!(world.get::<HumanPlayer>(leaver).is_none());
+
+        // The leaver's slot stays put so the joiner's index never shifts
+        let game = world.resource::<Game>();
+        assert_eq!(game.remote_player_roles, vec![None, Some(AiRole::Herald2)]);
+        assert_eq!(world.resource::<RosterChanges>().0, changes);
+    }
+
+    #[test]
+    fn joining_without_an_ai_spawns_a_human() {
+        let mut app = test_app();
+        let world = app.world_mut();
+        world.spawn((
+            Player::new("host".to_string()),
+            HumanPlayer {
+                index: 0,
+                role: AiRole::Virt1,
+            },
+        ));
+
+        let change = RosterChange::Joined {
+            index: LOCAL_PLAYERS,
+            role: AiRole::Ham2,
+        };
+        world
+            .run_system_once(
+                move |mut game: ResMut<Game>, mut hand_over: PlayerHandOver| {
+                    hand_over.hand_over_players(&mut game, change);
+                },
+            )
+            .unwrap();
+
+        let mut humans = world.query::<&HumanPlayer>();
+        let joined: Vec<_> = humans
+            .iter(world)
+            .filter(|human_player| human_player.index == LOCAL_PLAYERS)
+            .collect();
+        assert_eq!(joined.len(), 1);
+        assert_eq!(joined[0].role, AiRole::Ham2);
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiRole;
+use crate::game::{closest_game_speed, Difficulty, Game};
+use crate::input::InputBindings;
+
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_PATH: &str = "settings.ron";
+#[cfg(target_arch = "wasm32")]
+const SETTINGS_STORAGE_KEY: &str = "dragonfruitvoid-settings";
+
+/// Start menu choices that carry over between sessions. Fields missing from
+/// an older save fall back to their defaults
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub echo_enabled: bool,
+    pub hints_enabled: bool,
+    pub ai_enabled: bool,
+    pub ai_bars_enabled: bool,
+    pub puddles_enabled: bool,
+    pub greens_enabled: bool,
+    pub unlimited_range_enabled: bool,
+    pub audio_enabled: bool,
+    pub camera_follow_enabled: bool,
+    pub player_role: Option<AiRole>,
+    pub second_player_role: Option<AiRole>,
+    pub difficulty: Difficulty,
+    pub game_speed: f32,
+    pub bindings: InputBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings::from_game(&Game::default(), &InputBindings::default())
+    }
+}
+
+impl Settings {
+    pub fn from_game(game: &Game, bindings: &InputBindings) -> Settings {
+        Settings {
+            echo_enabled: game.echo_enabled,
+            hints_enabled: game.hints_enabled,
+            ai_enabled: game.ai_enabled,
+            ai_bars_enabled: game.ai_bars_enabled,
+            puddles_enabled: game.puddles_enabled,
+            greens_enabled: game.greens_enabled,
+            unlimited_range_enabled: game.unlimited_range_enabled,
+            audio_enabled: game.audio_enabled,
+            camera_follow_enabled: game.camera_follow_enabled,
+            player_role: game.player_role,
+            second_player_role: game.second_player_role,
+            difficulty: game.difficulty,
+            game_speed: game.game_speed,
+            bindings: bindings.clone(),
+        }
+    }
+
+    pub fn apply(&self, game: &mut Game, bindings: &mut InputBindings) {
+        game.echo_enabled = self.echo_enabled;
+        game.hints_enabled = self.hints_enabled;
+        game.ai_enabled = self.ai_enabled;
+        game.ai_bars_enabled = self.ai_bars_enabled;
+        game.puddles_enabled = self.puddles_enabled;
+        game.greens_enabled = self.greens_enabled;
+        game.unlimited_range_enabled = self.unlimited_range_enabled;
+        game.audio_enabled = self.audio_enabled;
+        game.camera_follow_enabled = self.camera_follow_enabled;
+        game.player_role = self.player_role;
+        // Player 2 plays on a gamepad next to a first player, never alone
+        game.second_player_role = self.player_role.and(self.second_player_role);
+        game.difficulty = self.difficulty;
+        game.game_speed = closest_game_speed(self.game_speed);
+        *bindings = self.bindings.clone();
+        bindings.add_missing_defaults();
+    }
+}
+
+/// Runs before the app exists, so there's no logger to warn through yet
+pub fn load_settings() -> Option<Settings> {
+    let contents = read_settings()?;
+    match ron::from_str(&contents) {
+        Ok(settings) => Some(settings),
+        Err(err) => {
+            eprintln!("could not parse settings, using defaults: {}", err);
+            None
+        }
+    }
+}
+
+pub fn save_settings(game: &Game, bindings: &InputBindings) {
+    match ron::to_string(&Settings::from_game(game, bindings)) {
+        Ok(contents) => write_settings(&contents),
+        Err(err) => warn!("could not serialize settings: {}", err),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_settings() -> Option<String> {
+    std::fs::read_to_string(SETTINGS_PATH).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_settings(contents: &str) {
+    if let Err(err) = std::fs::write(SETTINGS_PATH, contents) {
+        warn!("could not save settings to {}: {}", SETTINGS_PATH, err);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_settings() -> Option<String> {
+    local_storage()?
+        .get_item(SETTINGS_STORAGE_KEY)
+        .ok()
+        .flatten()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_settings(contents: &str) {
+    let Some(storage) = local_storage() else {
+        warn!("could not save settings, no local storage");
+        return;
+    };
+    if storage.set_item(SETTINGS_STORAGE_KEY, contents).is_err() {
+        warn!("could not save settings to local storage");
+    }
+}
@@ -0,0 +1,317 @@
+use bevy::{
+    asset::{LoadState, UntypedAssetId},
+    audio::AudioLoader,
+    input::InputPlugin,
+    prelude::*,
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+    window::ExitCondition,
+};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::add_phase_systems;
+use crate::ai::{AiPlayer, AiRole};
+use crate::audio::PhaseAudio;
+use crate::game::*;
+use crate::timeline::load_phase_timelines;
+
+pub const SIM_TIMESTEP: f32 = 1. / 60.;
+pub const SIM_MAX_DURATION: f32 = 300.;
+const SIM_MAX_LOADING_UPDATES: usize = 10000;
+
+const SIM_PHASES: [GameState; 11] = [
+    GameState::PurificationOne,
+    GameState::Jormag,
+    GameState::Primordus,
+    GameState::Kralkatorrik,
+    GameState::PurificationTwo,
+    GameState::Mordremoth,
+    GameState::Zhaitan,
+    GameState::PurificationThree,
+    GameState::SooWonOne,
+    GameState::PurificationFour,
+    GameState::SooWonTwo,
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SimulationResult {
+    Cleared,
+    Failed,
+    TimedOut,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlayerOutcome {
+    pub name: String,
+    pub role: Option<AiRole>,
+    pub damage_taken: f32,
+    pub death_cause: Option<&'static str>,
+    pub dead: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct SimulationOutcome {
+    pub result: SimulationResult,
+    pub elapsed: f32,
+    pub players: Vec<PlayerOutcome>,
+}
+
+#[derive(Resource, Default)]
+struct SimulationDeaths(Vec<(Entity, Option<&'static str>, f32)>);
+
+fn record_deaths_system(
+    mut death_events: EventReader<PlayerDeathEvent>,
+    mut deaths: ResMut<SimulationDeaths>,
+) {
+    for event in death_events.read() {
+        deaths
+            .0
+            .push((event.player, event.reason, event.damage_taken));
+    }
+}
+
+fn despawn_audio_system(mut commands: Commands, audio: Query<Entity, With<PhaseAudio>>) {
+    for entity in &audio {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn simulation_game() -> Game {
+    Game {
+        hints_enabled: false,
+        audio_enabled: false,
+        player_role: None,
+        ..default()
+    }
+}
+
+/// An app with every phase system and no window, stepping a fixed
+/// [`SIM_TIMESTEP`] per update, that has finished loading the timelines
+pub fn headless_app(game: Game) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        InputPlugin,
+        WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            ..default()
+        },
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .init_asset::<Image>()
+    .init_asset::<Font>()
+    .init_asset::<AudioSource>()
+    .init_asset_loader::<AudioLoader>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        SIM_TIMESTEP,
+    )))
+    .insert_resource(game)
+    .init_resource::<SimulationDeaths>()
+    .add_systems(
+        Update,
+        (record_deaths_system, despawn_audio_system).after(PhaseSet::UpdatePhase),
+    );
+
+    add_phase_systems(&mut app);
+    // Two conflicting systems without an order could swap between runs, so
+    // tests check for them before the schedule is first built below
+    #[cfg(test)]
+    app.edit_schedule(Update, |schedule| {
+        use bevy::ecs::schedule::{LogLevel, ScheduleBuildSettings};
+        schedule.set_build_settings(ScheduleBuildSettings {
+            ambiguity_detection: LogLevel::Error,
+            ..default()
+        });
+    });
+
+    let timelines = load_phase_timelines(app.world().resource::<AssetServer>());
+    let timeline_ids: Vec<UntypedAssetId> =
+        timelines.0.values().map(|h| h.id().untyped()).collect();
+    app.insert_resource(timelines);
+
+    for _ in 0..SIM_MAX_LOADING_UPDATES {
+        app.update();
+        let asset_server = app.world().resource::<AssetServer>();
+        let load_states: Vec<Option<LoadState>> = timeline_ids
+            .iter()
+            .map(|id| asset_server.get_load_state(*id))
+            .collect();
+        if let Some(Some(LoadState::Failed(err))) = load_states
+            .iter()
+            .find(|ls| matches!(ls, Some(LoadState::Failed(_))))
+        {
+            panic!("failed to load phase timeline: {}", err);
+        }
+        if load_states
+            .iter()
+            .all(|ls| matches!(ls, Some(LoadState::Loaded)))
+        {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    app
+}
+
+pub fn run_headless(game_state: GameState, game: Game) -> SimulationOutcome {
+    let mut app = headless_app(game);
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(game_state);
+    app.world_mut()
+        .resource_mut::<NextState<MenuState>>()
+        .set(MenuState::Unpaused);
+
+    let mut players: BTreeMap<Entity, PlayerOutcome> = BTreeMap::new();
+    let mut player_query = app
+        .world_mut()
+        .query::<(Entity, &Player, Option<&AiPlayer>)>();
+
+    let result = loop {
+        app.update();
+
+        for (entity, player, ai_player) in player_query.iter(app.world()) {
+            let outcome = players.entry(entity).or_insert_with(|| PlayerOutcome {
+                name: player.name.clone(),
+                role: ai_player.map(|ai_player| ai_player.role),
+                damage_taken: 0.,
+                death_cause: None,
+                dead: false,
+            });
+            outcome.damage_taken = player.damage_taken;
+        }
+
+        for (entity, reason, damage_taken) in app
+            .world_mut()
+            .resource_mut::<SimulationDeaths>()
+            .0
+            .drain(..)
+        {
+            if let Some(outcome) = players.get_mut(&entity) {
+                outcome.damage_taken = damage_taken;
+                outcome.death_cause = reason;
+                outcome.dead = true;
+            }
+        }
+
+        match app.world().resource::<State<MenuState>>().get() {
+            MenuState::Success => break SimulationResult::Cleared,
+            MenuState::Failure => break SimulationResult::Failed,
+            _ => {}
+        }
+
+        let elapsed = app.world().resource::<Game>().time_elapsed.elapsed_secs();
+        if elapsed > SIM_MAX_DURATION {
+            break SimulationResult::TimedOut;
+        }
+    };
+
+    SimulationOutcome {
+        result,
+        elapsed: app.world().resource::<Game>().time_elapsed.elapsed_secs(),
+        players: players.into_values().collect(),
+    }
+}
+
+fn parse_game_state(name: &str) -> Option<GameState> {
+    let name = name.to_lowercase().replace(['-', '_'], "");
+    SIM_PHASES
+        .into_iter()
+        .find(|state| format!("{:?}", state).to_lowercase() == name)
+}
+
+/// Runs `--simulate <phase> [--runs <n>] [--start-at <secs>] [--difficulty <difficulty>]` from
+/// the command line, returning false if the game should start normally instead
+pub fn run_from_args() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(phase_index) = args.iter().position(|arg| arg == "--simulate") else {
+        return false;
+    };
+
+    let Some(game_state) = args
+        .get(phase_index + 1)
+        .and_then(|name| parse_game_state(name))
+    else {
+        let phases: Vec<String> = SIM_PHASES
+            .iter()
+            .map(|state| format!("{:?}", state))
+            .collect();
+        eprintln!("--simulate expects one of: {}", phases.join(", "));
+        return true;
+    };
+
+    let runs: usize = args
+        .iter()
+        .position(|arg| arg == "--runs")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|runs| runs.parse().ok())
+        .unwrap_or(1);
+
+    let start_time: f32 = args
+        .iter()
+        .position(|arg| arg == "--start-at")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(0.);
+
+    let difficulty = match args
+        .iter()
+        .position(|arg| arg == "--difficulty")
+        .and_then(|i| args.get(i + 1))
+        .map(|name| name.to_lowercase())
+        .as_deref()
+    {
+        None | Some("cm") | Some("challengemode") => Difficulty::ChallengeMode,
+        Some("normal") => Difficulty::Normal,
+        Some("story") => Difficulty::Story,
+        Some(other) => {
+            eprintln!("--difficulty expects story, normal or cm, not {}", other);
+            return true;
+        }
+    };
+
+    let mut cleared = 0;
+    for run in 1..=runs {
+        let game = Game {
+            start_time,
+            difficulty,
+            ..simulation_game()
+        };
+        let outcome = run_headless(game_state, game);
+        if outcome.result == SimulationResult::Cleared {
+            cleared += 1;
+        }
+
+        println!(
+            "run {}: {:?} after {:.1}s",
+            run, outcome.result, outcome.elapsed
+        );
+        for player in &outcome.players {
+            let role = player
+                .role
+                .map(|role| format!("{:?}", role))
+                .unwrap_or(player.name.clone());
+            let status = match (&player.dead, &player.death_cause) {
+                (true, Some(cause)) => format!("died to {}", cause),
+                (true, None) => "died".to_string(),
+                (false, _) => "alive".to_string(),
+            };
+            println!(
+                "  {}: {:.0} damage taken, {}",
+                role, player.damage_taken, status
+            );
+        }
+    }
+    println!("{:?}: cleared {}/{}", game_state, cleared, runs);
+
+    true
+}
@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+
+use crate::ai::AiRole;
+use crate::boons::{allies_in_range, Boon};
+use crate::downed::Downed;
+use crate::game::{HumanPlayer, Player, BULLET_COOLDOWN};
+use crate::input::{HumanInputs, InputAction};
+
+const GROUP_HEAL_AMOUNT: f32 = 40.;
+
+/// Listed in the order they sit on the skill bar
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Skill {
+    Pull,
+    Blink,
+    Portal,
+    /// Heals the closest few allies
+    GroupHeal,
+    /// Quickness, protection and stability on the closest few allies
+    Facet,
+}
+
+impl Skill {
+    pub fn action(&self) -> InputAction {
+        match self {
+            Skill::Pull => InputAction::Pull,
+            Skill::Blink => InputAction::Blink,
+            Skill::Portal => InputAction::Portal,
+            Skill::GroupHeal | Skill::Facet => InputAction::Utility,
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Skill::Pull => "pull.png",
+            Skill::Blink => "blink.png",
+            Skill::Portal => "portal.png",
+            Skill::GroupHeal => "ham.png",
+            Skill::Facet => "herald.png",
+        }
+    }
+}
+
+/// What the human player can do as the role they are stepping in for
+#[derive(Component, Clone, Debug)]
+pub struct SkillKit {
+    pub shoot_cooldown: f32,
+    pub bullet_damage_multiplier: f32,
+    pub skills: Vec<Skill>,
+    pub utility_cooldown: f32,
+}
+
+impl SkillKit {
+    pub fn for_role(role: &AiRole) -> SkillKit {
+        let mut kit = match role {
+            AiRole::Virt1 | AiRole::Virt2 => SkillKit {
+                shoot_cooldown: BULLET_COOLDOWN,
+                bullet_damage_multiplier: 1.,
+                skills: vec![Skill::Pull, Skill::Portal],
+                utility_cooldown: 0.,
+            },
+            AiRole::Herald1 | AiRole::Herald2 => SkillKit {
+                shoot_cooldown: BULLET_COOLDOWN,
+                bullet_damage_multiplier: 1.,
+                skills: vec![Skill::Pull, Skill::Facet],
+                utility_cooldown: 15.,
+            },
+            AiRole::Ham1 | AiRole::Ham2 => SkillKit {
+                shoot_cooldown: BULLET_COOLDOWN,
+                bullet_damage_multiplier: 1.,
+                skills: vec![Skill::GroupHeal],
+                utility_cooldown: 20.,
+            },
+            // Quick weak hits that add up to a bit more than everyone else
+            AiRole::Dps1 | AiRole::Dps2 | AiRole::Dps3 | AiRole::Dps4 => SkillKit {
+                shoot_cooldown: BULLET_COOLDOWN / 2.,
+                bullet_damage_multiplier: 0.6,
+                skills: vec![Skill::Pull],
+                utility_cooldown: 0.,
+            },
+        };
+        if role.is_blink_enabled() {
+            kit.skills.push(Skill::Blink);
+            kit.skills.sort();
+        }
+        kit
+    }
+
+    pub fn has(&self, skill: Skill) -> bool {
+        self.skills.contains(&skill)
+    }
+
+    pub fn utility(&self) -> Option<Skill> {
+        self.skills
+            .iter()
+            .copied()
+            .find(|skill| skill.action() == InputAction::Utility)
+    }
+
+    /// Sets up the player's cooldowns to match the kit
+    pub fn equip(&self, player: &mut Player) {
+        player.shoot_cooldown = Timer::from_seconds(self.shoot_cooldown, TimerMode::Once);
+        player.utility_cooldown = Timer::from_seconds(self.utility_cooldown, TimerMode::Once);
+        let duration = player.utility_cooldown.duration();
+        player.utility_cooldown.tick(duration);
+    }
+}
+
+pub fn utility_skill_system(
+    inputs: Res<HumanInputs>,
+    casters: Query<(Entity, &Transform, &SkillKit, &HumanPlayer), Without<Downed>>,
+    mut players: Query<(&Transform, &mut Player), Without<Downed>>,
+) {
+    for (entity_caster, transform_caster, kit, human_player) in &casters {
+        if !inputs.get(human_player).pressed(InputAction::Utility) {
+            continue;
+        }
+        let Some(skill) = kit.utility() else {
+            continue;
+        };
+        let Ok((_, mut caster)) = players.get_mut(entity_caster) else {
+            continue;
+        };
+        if !caster.utility_cooldown.finished() {
+            continue;
+        }
+        caster.utility_cooldown.reset();
+
+        for mut ally in allies_in_range(transform_caster.translation, players.iter_mut()) {
+            match skill {
+                Skill::GroupHeal => ally.heal(GROUP_HEAL_AMOUNT),
+                Skill::Facet => {
+                    ally.boons.apply(Boon::Quickness, 6.);
+                    ally.boons.apply(Boon::Protection, 4.);
+                    ally.boons.apply(Boon::Stability, 6.);
+                    ally.boons.apply(Boon::Stability, 6.);
+                }
+                Skill::Pull | Skill::Blink | Skill::Portal => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kits_match_each_role_family() {
+        let cases = [
+            (
+                AiRole::Virt1,
+                vec![Skill::Pull, Skill::Blink, Skill::Portal],
+            ),
+            (AiRole::Herald1, vec![Skill::Pull, Skill::Facet]),
+            (AiRole::Ham1, vec![Skill::Blink, Skill::GroupHeal]),
+            (AiRole::Dps1, vec![Skill::Pull]),
+        ];
+        for (role, skills) in cases {
+            assert_eq!(SkillKit::for_role(&role).skills, skills, "{role:?}");
+        }
+    }
+
+    #[test]
+    fn blink_follows_the_role() {
+        for role in AiRole::ALL {
+            assert_eq!(
+                SkillKit::for_role(&role).has(Skill::Blink),
+                role.is_blink_enabled(),
+                "{role:?}"
+            );
+        }
+    }
+}
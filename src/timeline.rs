@@ -0,0 +1,236 @@
+use bevy::asset::{io::Reader, AssetLoader, LoadContext};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::game::GameState;
+use crate::greens::GreenSpawn;
+
+pub const TIMELINE_VERSION: u32 = 1;
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct PhaseTimeline {
+    pub version: u32,
+    pub boss_name: String,
+    #[serde(default)]
+    pub greens: Vec<GreenSpawn>,
+    #[serde(default)]
+    pub puddles: Vec<f32>,
+    #[serde(default)]
+    pub spreads: Vec<f32>,
+    #[serde(default)]
+    pub mechanics: Vec<Mechanic>,
+    #[serde(default)]
+    pub boss_moves: Vec<BossMove>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveSpawn {
+    pub start: f32,
+    pub position: Vec2,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TailSlamSpawn {
+    pub start: f32,
+    /// Where the tail lands while the boss is at its starting pose
+    pub position: Vec2,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BossMove {
+    pub start: f32,
+    pub position: Vec2,
+    /// Defaults to looking at the middle of the platform
+    #[serde(default)]
+    pub facing: Option<Vec2>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct NoodleSpawn {
+    pub start: f32,
+    pub positions: Vec<Vec2>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FrostBeamSpawn {
+    pub start: f32,
+    pub path: Vec<Vec2>,
+    pub speed: f32,
+    pub width: f32,
+    pub damage: f32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum Mechanic {
+    Chomps {
+        starts: Vec<f32>,
+    },
+    MiniChomps {
+        starts: Vec<f32>,
+    },
+    DoubleLines {
+        starts: Vec<f32>,
+    },
+    MidLines {
+        starts: Vec<f32>,
+    },
+    Waves {
+        waves: Vec<WaveSpawn>,
+    },
+    Spews {
+        starts: Vec<f32>,
+        detonation: f32,
+        linger: Option<f32>,
+        radius_scale: f32,
+    },
+    Fears {
+        starts: Vec<f32>,
+    },
+    Noodles {
+        spawns: Vec<NoodleSpawn>,
+    },
+    ClawSwipes {
+        starts: Vec<f32>,
+    },
+    TailSlams {
+        slams: Vec<TailSlamSpawn>,
+    },
+    Bites {
+        starts: Vec<f32>,
+    },
+    FrostBeams {
+        beams: Vec<FrostBeamSpawn>,
+    },
+    RotatingSoups,
+    Wyvern {
+        position: Vec2,
+    },
+    Goliath {
+        position: Vec2,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum PhaseTimelineLoaderError {
+    #[error("could not read timeline: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse timeline: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("unsupported timeline version {0}, expected {TIMELINE_VERSION}")]
+    UnsupportedVersion(u32),
+}
+
+#[derive(Default)]
+pub struct PhaseTimelineLoader;
+
+impl PhaseTimelineLoader {
+    pub fn parse(bytes: &[u8]) -> Result<PhaseTimeline, PhaseTimelineLoaderError> {
+        let timeline = ron::de::from_bytes::<PhaseTimeline>(bytes)?;
+        if timeline.version != TIMELINE_VERSION {
+            return Err(PhaseTimelineLoaderError::UnsupportedVersion(
+                timeline.version,
+            ));
+        }
+        Ok(timeline)
+    }
+}
+
+impl AssetLoader for PhaseTimelineLoader {
+    type Asset = PhaseTimeline;
+    type Settings = ();
+    type Error = PhaseTimelineLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        PhaseTimelineLoader::parse(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["timeline.ron"]
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PhaseTimelines(pub HashMap<GameState, Handle<PhaseTimeline>>);
+
+pub fn timeline_path(game_state: GameState) -> Option<&'static str> {
+    match game_state {
+        GameState::Jormag => Some("timelines/jormag.timeline.ron"),
+        GameState::Primordus => Some("timelines/primordus.timeline.ron"),
+        GameState::Kralkatorrik => Some("timelines/kralkatorrik.timeline.ron"),
+        GameState::Mordremoth => Some("timelines/mordremoth.timeline.ron"),
+        GameState::Zhaitan => Some("timelines/zhaitan.timeline.ron"),
+        GameState::SooWonOne => Some("timelines/soowonone.timeline.ron"),
+        GameState::SooWonTwo => Some("timelines/soowontwo.timeline.ron"),
+        _ => None,
+    }
+}
+
+pub const BOSS_PHASES: [GameState; 7] = [
+    GameState::Jormag,
+    GameState::Primordus,
+    GameState::Kralkatorrik,
+    GameState::Mordremoth,
+    GameState::Zhaitan,
+    GameState::SooWonOne,
+    GameState::SooWonTwo,
+];
+
+pub fn load_phase_timelines(asset_server: &AssetServer) -> PhaseTimelines {
+    let mut timelines = PhaseTimelines::default();
+    for game_state in BOSS_PHASES {
+        let Some(path) = timeline_path(game_state) else {
+            continue;
+        };
+        timelines.0.insert(game_state, asset_server.load(path));
+    }
+    timelines
+}
+
+#[derive(SystemParam)]
+pub struct CurrentTimeline<'w> {
+    game_state: Res<'w, State<GameState>>,
+    timelines: Res<'w, PhaseTimelines>,
+    timeline_assets: Res<'w, Assets<PhaseTimeline>>,
+}
+
+impl CurrentTimeline<'_> {
+    pub fn get(&self) -> Option<&PhaseTimeline> {
+        self.timelines
+            .0
+            .get(self.game_state.get())
+            .and_then(|handle| self.timeline_assets.get(handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_timelines_parse() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/timelines");
+        let mut parsed = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if !path.to_string_lossy().ends_with(".timeline.ron") {
+                continue;
+            }
+            let bytes = std::fs::read(&path).unwrap();
+            if let Err(err) = PhaseTimelineLoader::parse(&bytes) {
+                panic!("{}: {}", path.display(), err);
+            }
+            parsed += 1;
+        }
+        assert_eq!(parsed, 7);
+    }
+}
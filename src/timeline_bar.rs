@@ -0,0 +1,225 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::aoes::Aoe;
+use crate::boss_phase::{PuddleSpawn, SpreadAoeSpawn};
+use crate::camera::hud_layer;
+use crate::game::{PhaseEntity, HEIGHT, LAYER_TEXT, LAYER_UI, WIDTH};
+use crate::greens::StackGreen;
+use crate::ledger::DamageSource;
+use crate::waves::Wave;
+
+/// How far ahead the timeline bar looks
+pub const TIMELINE_BAR_WINDOW_S: f32 = 30.;
+const TIMELINE_BAR_MARKERS: usize = 12;
+const TIMELINE_BAR_START_X: f32 = -WIDTH / 2. + 60.;
+const TIMELINE_BAR_END_X: f32 = WIDTH / 2. - 60.;
+const TIMELINE_BAR_Y: f32 = HEIGHT / 2. - 24.;
+const TIMELINE_BAR_ICON_SIZE: f32 = 24.;
+/// Mechanics of the same kind this close together share a marker, so that
+/// staggered ones like claw swipe bounces and line segments show up once
+const TIMELINE_BAR_MERGE_S: f32 = 3.;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UpcomingKind {
+    Green,
+    Puddle,
+    Spread,
+    Wave,
+    Aoe(&'static str),
+}
+
+impl UpcomingKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UpcomingKind::Green => "green",
+            UpcomingKind::Puddle => "reds",
+            UpcomingKind::Spread => "spread",
+            UpcomingKind::Wave => "wave",
+            UpcomingKind::Aoe(name) => name,
+        }
+    }
+
+    fn icon(&self) -> (&'static str, Color) {
+        match self {
+            UpcomingKind::Green => ("ring.png", Color::srgb(0., 0.9, 0.)),
+            UpcomingKind::Puddle => ("ring.png", Color::srgb(0.9, 0., 0.)),
+            UpcomingKind::Spread => ("ring.png", Color::srgb(0.9, 0.9, 0.)),
+            UpcomingKind::Wave => ("wave.png", Color::WHITE),
+            UpcomingKind::Aoe(_) => ("ring.png", Color::srgb(0.9, 0.5, 0.)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UpcomingMechanic {
+    pub kind: UpcomingKind,
+    /// Seconds until it shows up in the arena
+    pub remaining: f32,
+}
+
+/// Everything scheduled within the next [`TIMELINE_BAR_WINDOW_S`] seconds,
+/// soonest first
+#[derive(Resource, Default)]
+pub struct UpcomingMechanics(pub Vec<UpcomingMechanic>);
+
+#[derive(Component)]
+pub struct TimelineBarMarker(usize);
+
+#[derive(Component)]
+pub struct TimelineBarIcon;
+
+#[derive(Component)]
+pub struct TimelineBarText;
+
+pub fn setup_timeline_bar(commands: &mut Commands, asset_server: &Res<AssetServer>) {
+    let text_font = TextFont {
+        font: asset_server.load("trebuchet_ms.ttf"),
+        font_size: 16.,
+        ..default()
+    };
+
+    commands.spawn((
+        Sprite {
+            color: Color::srgba(0., 0., 0., 0.5),
+            custom_size: Some(Vec2::new(TIMELINE_BAR_END_X - TIMELINE_BAR_START_X, 6.)),
+            anchor: Anchor::CenterLeft,
+            ..default()
+        },
+        Transform::from_xyz(TIMELINE_BAR_START_X, TIMELINE_BAR_Y, LAYER_UI - 0.1),
+        hud_layer(),
+        PhaseEntity,
+    ));
+
+    for i in 0..TIMELINE_BAR_MARKERS {
+        // Alternate label rows so that neighbouring markers don't overlap
+        let text_y = if i % 2 == 0 { -22. } else { -38. };
+
+        commands
+            .spawn((
+                Transform::from_xyz(TIMELINE_BAR_START_X, TIMELINE_BAR_Y, LAYER_UI),
+                Visibility::Hidden,
+                TimelineBarMarker(i),
+                hud_layer(),
+                PhaseEntity,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Sprite {
+                        custom_size: Some(Vec2::splat(TIMELINE_BAR_ICON_SIZE)),
+                        ..default()
+                    },
+                    TimelineBarIcon,
+                    hud_layer(),
+                ));
+                parent.spawn((
+                    Text2d::new(""),
+                    text_font.clone(),
+                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                    Anchor::Center,
+                    Transform::from_xyz(0., text_y, LAYER_TEXT - LAYER_UI),
+                    TimelineBarText,
+                    hud_layer(),
+                ));
+            });
+    }
+}
+
+pub fn upcoming_mechanics_system(
+    mut upcoming: ResMut<UpcomingMechanics>,
+    puddle_spawns: Query<&PuddleSpawn>,
+    spread_aoe_spawns: Query<&SpreadAoeSpawn>,
+    greens: Query<&StackGreen>,
+    waves: Query<&Wave>,
+    aoes: Query<(&Aoe, &DamageSource)>,
+) {
+    let mut mechanics = vec![];
+
+    for puddle_spawn in &puddle_spawns {
+        mechanics.push((UpcomingKind::Puddle, &puddle_spawn.visibility_start));
+    }
+    for spread_aoe_spawn in &spread_aoe_spawns {
+        for timer in &spread_aoe_spawn.timers {
+            mechanics.push((UpcomingKind::Spread, timer));
+        }
+    }
+    for green in &greens {
+        mechanics.push((UpcomingKind::Green, &green.visibility_start));
+    }
+    for wave in &waves {
+        mechanics.push((UpcomingKind::Wave, &wave.visibility_start));
+    }
+    for (aoe, source) in &aoes {
+        if let Some(timer) = &aoe.visibility_start {
+            mechanics.push((UpcomingKind::Aoe(source.0), timer));
+        }
+    }
+
+    upcoming.0.clear();
+    for (kind, timer) in mechanics {
+        let remaining = timer.remaining_secs();
+        if timer.finished() || remaining > TIMELINE_BAR_WINDOW_S {
+            continue;
+        }
+        upcoming.0.push(UpcomingMechanic { kind, remaining });
+    }
+
+    upcoming
+        .0
+        .sort_by(|a, b| a.remaining.total_cmp(&b.remaining));
+    let mut merged: Vec<UpcomingMechanic> = vec![];
+    for mechanic in upcoming.0.drain(..) {
+        let duplicate = merged.iter().any(|other| {
+            other.kind == mechanic.kind
+                && mechanic.remaining - other.remaining < TIMELINE_BAR_MERGE_S
+        });
+        if !duplicate {
+            merged.push(mechanic);
+        }
+    }
+    upcoming.0 = merged;
+}
+
+pub fn timeline_bar_system(
+    asset_server: Res<AssetServer>,
+    upcoming: Res<UpcomingMechanics>,
+    mut markers: Query<(
+        &TimelineBarMarker,
+        &mut Transform,
+        &mut Visibility,
+        &Children,
+    )>,
+    mut icons: Query<&mut Sprite, With<TimelineBarIcon>>,
+    mut texts: Query<&mut Text2d, With<TimelineBarText>>,
+) {
+    let bar_width = TIMELINE_BAR_END_X - TIMELINE_BAR_START_X;
+
+    for (marker, mut transform, mut visibility, children) in &mut markers {
+        let Some(mechanic) = upcoming.0.get(marker.0) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        *visibility = Visibility::Inherited;
+        transform.translation.x =
+            TIMELINE_BAR_START_X + mechanic.remaining / TIMELINE_BAR_WINDOW_S * bar_width;
+
+        // Only touch the icon and text when they change to avoid relaying out
+        // the text every frame
+        let (image, color) = mechanic.kind.icon();
+        let image = asset_server.load(image);
+        let label = format!("{} {}", mechanic.kind.label(), mechanic.remaining.ceil());
+        for &child in children.iter() {
+            if let Ok(mut sprite) = icons.get_mut(child) {
+                if sprite.image != image || sprite.color != color {
+                    sprite.image = image.clone();
+                    sprite.color = color;
+                }
+            }
+            if let Ok(mut text) = texts.get_mut(child) {
+                if text.0 != label {
+                    text.0 = label.clone();
+                }
+            }
+        }
+    }
+}
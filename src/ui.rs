@@ -1,8 +1,22 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, sprite::Anchor};
 
-use crate::ai::AiPlayer;
-use crate::game::Player;
+use crate::ai::{AiPlayer, AiRole};
+use crate::camera::hud_layer;
+use crate::downed::Downed;
+use crate::game::{Game, HumanPlayer, PhaseEntity, Player, HEIGHT, LAYER_TEXT, LAYER_UI, WIDTH};
 use crate::mobs::{Boss, Hp};
+use crate::phase::icon_for_role;
+
+const SQUAD_FRAME_X: f32 = WIDTH / 2. - 170.;
+const SQUAD_FRAME_TOP_Y: f32 = HEIGHT / 2. - 100.;
+const SQUAD_FRAME_ROW_HEIGHT: f32 = 36.;
+const SQUAD_FRAME_ICON_SIZE: f32 = 28.;
+const SQUAD_FRAME_BAR_X: f32 = 20.;
+const SQUAD_FRAME_BAR_WIDTH: f32 = 140.;
+const SQUAD_FRAME_HP_HEIGHT: f32 = 14.;
+const SQUAD_FRAME_COOLDOWN_HEIGHT: f32 = 5.;
+const SQUAD_FRAME_HP_COLOR: Color = Color::srgb(0.2, 0.8, 0.2);
+const SQUAD_FRAME_DOWNED_COLOR: Color = Color::srgb(0.8, 0.2, 0.2);
 
 pub enum TextValue {
     Hp,
@@ -10,6 +24,7 @@ pub enum TextValue {
     CooldownBlink,
     CooldownPortal,
     CooldownPull,
+    CooldownUtility,
     StatusJump,
 }
 
@@ -17,6 +32,8 @@ pub enum TextValue {
 pub struct TextDisplay {
     pub value: TextValue,
     pub sprite: Option<Entity>,
+    /// Index of the [`HumanPlayer`] whose HUD this is part of
+    pub human_player: usize,
 }
 
 #[derive(Component)]
@@ -52,6 +69,30 @@ pub struct PlayerCooldownBar {
     pub cooldown: PlayerCooldown,
 }
 
+pub fn role_cooldowns(role: &AiRole) -> Vec<PlayerCooldown> {
+    let mut cooldowns = vec![PlayerCooldown::Dodge, PlayerCooldown::Jump];
+    if role.is_blink_enabled() {
+        cooldowns.push(PlayerCooldown::Blink);
+    }
+    cooldowns
+}
+
+/// One player's row in the squad panel
+#[derive(Component)]
+pub struct SquadFrame {
+    pub player: Entity,
+}
+
+#[derive(Component)]
+pub struct SquadFrameStatus {
+    pub player: Entity,
+}
+
+#[derive(Component)]
+pub struct SquadFrameHpBar {
+    pub player: Entity,
+}
+
 #[derive(Component)]
 pub struct Gauge {
     pub value: f32,
@@ -95,12 +136,15 @@ fn set_cooldown_text_display(
 }
 
 pub fn player_text_system(
-    players: Query<&Player, Without<AiPlayer>>,
+    players: Query<(&Player, &HumanPlayer)>,
     mut text_displays: Query<(&mut Text2d, &mut TextColor, &TextDisplay)>,
     mut sprites: Query<&mut Sprite>,
 ) {
-    for player in &players {
+    for (player, human_player) in &players {
         for (mut text, mut text_color, text_display) in &mut text_displays {
+            if text_display.human_player != human_player.index {
+                continue;
+            }
             match text_display.value {
                 TextValue::Hp => {
                     let hp = player.get_hp().clamp(0., 100.);
@@ -151,6 +195,15 @@ pub fn player_text_system(
                         &mut sprites,
                     );
                 }
+                TextValue::CooldownUtility => {
+                    set_cooldown_text_display(
+                        &player.utility_cooldown,
+                        &mut text,
+                        &mut text_color,
+                        text_display,
+                        &mut sprites,
+                    );
+                }
             }
         }
     }
@@ -226,3 +279,196 @@ pub fn update_gauge_bars_system(
         }
     }
 }
+
+fn spawn_gauge(
+    parent: &mut ChildBuilder,
+    gauge_source: impl Bundle,
+    bar_marker: impl Bundle,
+    position: Vec2,
+    size: Vec2,
+    color: Color,
+) {
+    parent
+        .spawn((
+            Gauge {
+                value: 1.,
+                hide_when_full: false,
+            },
+            gauge_source,
+            Transform::from_translation(position.extend(0.)),
+            Visibility::default(),
+            hud_layer(),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Sprite {
+                    color,
+                    custom_size: Some(size),
+                    anchor: Anchor::CenterLeft,
+                    ..default()
+                },
+                Transform::from_xyz(0., 0., 0.2),
+                GaugeBar,
+                bar_marker,
+                hud_layer(),
+            ));
+
+            parent.spawn((
+                Sprite {
+                    color: Color::srgb(0.3, 0.3, 0.3),
+                    custom_size: Some(size),
+                    anchor: Anchor::CenterLeft,
+                    ..default()
+                },
+                Transform::from_xyz(0., 0., 0.1),
+                hud_layer(),
+            ));
+        });
+}
+
+/// Adds a row to the squad panel for every player that doesn't have one yet,
+/// ordered by role
+pub fn squad_frames_spawn_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game: Res<Game>,
+    players: Query<(Entity, AnyOf<(&AiPlayer, &HumanPlayer)>)>,
+    frames: Query<&SquadFrame>,
+) {
+    let text_font = TextFont {
+        font: asset_server.load("trebuchet_ms.ttf"),
+        font_size: 13.,
+        ..default()
+    };
+
+    for (entity_player, (ai_player, human_player)) in &players {
+        if frames.iter().any(|frame| frame.player == entity_player) {
+            continue;
+        }
+        let Some(role) = ai_player
+            .map(|ai_player| ai_player.role)
+            .or(human_player.map(|human_player| human_player.role))
+        else {
+            continue;
+        };
+        let slot = AiRole::ALL.iter().position(|r| *r == role).unwrap_or(0);
+        let y = SQUAD_FRAME_TOP_Y - SQUAD_FRAME_ROW_HEIGHT * slot as f32;
+
+        let cooldowns = role_cooldowns(&role);
+        let name = match human_player {
+            Some(human_player) if !human_player.is_local() => {
+                format!("{} (online)", role.to_string())
+            }
+            Some(human_player) if game.second_player_role.is_some() => {
+                format!("{} (P{})", role.to_string(), human_player.index + 1)
+            }
+            Some(_) => format!("{} (you)", role.to_string()),
+            None => role.to_string(),
+        };
+
+        commands
+            .spawn((
+                Transform::from_xyz(SQUAD_FRAME_X, y, LAYER_UI),
+                Visibility::default(),
+                SquadFrame {
+                    player: entity_player,
+                },
+                hud_layer(),
+                PhaseEntity,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Sprite {
+                        custom_size: Some(Vec2::splat(SQUAD_FRAME_ICON_SIZE)),
+                        image: asset_server.load(icon_for_role(&role)),
+                        ..default()
+                    },
+                    hud_layer(),
+                ));
+
+                spawn_gauge(
+                    parent,
+                    PlayerHealthbar {
+                        player: entity_player,
+                    },
+                    SquadFrameHpBar {
+                        player: entity_player,
+                    },
+                    Vec2::new(SQUAD_FRAME_BAR_X, 4.),
+                    Vec2::new(SQUAD_FRAME_BAR_WIDTH, SQUAD_FRAME_HP_HEIGHT),
+                    SQUAD_FRAME_HP_COLOR,
+                );
+
+                let cooldown_width = SQUAD_FRAME_BAR_WIDTH / cooldowns.len() as f32;
+                for (i, cooldown) in cooldowns.into_iter().enumerate() {
+                    let color = cooldown.color();
+                    spawn_gauge(
+                        parent,
+                        PlayerCooldownBar {
+                            player: entity_player,
+                            cooldown,
+                        },
+                        (),
+                        Vec2::new(
+                            SQUAD_FRAME_BAR_X + cooldown_width * i as f32,
+                            -SQUAD_FRAME_HP_HEIGHT / 2. + 1.,
+                        ),
+                        Vec2::new(cooldown_width - 2., SQUAD_FRAME_COOLDOWN_HEIGHT),
+                        color,
+                    );
+                }
+
+                parent.spawn((
+                    Text2d::new(name),
+                    text_font.clone(),
+                    TextColor(Color::srgb(1., 1., 1.)),
+                    Anchor::CenterLeft,
+                    Transform::from_xyz(SQUAD_FRAME_BAR_X + 4., 4., LAYER_TEXT - LAYER_UI),
+                    hud_layer(),
+                ));
+
+                parent.spawn((
+                    Text2d::new(""),
+                    text_font.clone(),
+                    TextColor(Color::srgb(1., 0.9, 0.2)),
+                    Anchor::CenterRight,
+                    Transform::from_xyz(
+                        SQUAD_FRAME_BAR_X + SQUAD_FRAME_BAR_WIDTH - 4.,
+                        4.,
+                        LAYER_TEXT - LAYER_UI,
+                    ),
+                    SquadFrameStatus {
+                        player: entity_player,
+                    },
+                    hud_layer(),
+                ));
+            });
+    }
+}
+
+pub fn squad_frames_status_system(
+    players: Query<(&Player, Has<Downed>)>,
+    mut statuses: Query<(&SquadFrameStatus, &mut Text2d)>,
+    mut hp_bars: Query<(&SquadFrameHpBar, &mut Sprite)>,
+) {
+    for (status, mut text) in &mut statuses {
+        let label = match players.get(status.player) {
+            Ok((_, true)) => "DOWN".to_string(),
+            Ok((player, false)) => player.boons.label(),
+            Err(_) => "DEAD".to_string(),
+        };
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+
+    for (hp_bar, mut sprite) in &mut hp_bars {
+        let color = match players.get(hp_bar.player).map(|(_, downed)| downed) {
+            Ok(false) => SQUAD_FRAME_HP_COLOR,
+            Ok(true) | Err(_) => SQUAD_FRAME_DOWNED_COLOR,
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
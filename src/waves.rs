@@ -1,5 +1,6 @@
 use crate::{
     audio::{play_sfx, Sfx, SfxSource},
+    collisions::CollisionShape,
     game::*,
 };
 use bevy::prelude::*;
@@ -23,6 +24,16 @@ pub const WAVE_MAX_RADIUS: f32 = WIDTH / 2.;
 pub const WAVE_GROWTH_DURATION: f32 = 3.2;
 pub const WAVE_DAMAGE: f32 = 75.;
 
+/// The ring a wave hits with at its current size. Safe in the "eye" of the
+/// wave and once it has passed
+pub fn wave_shape(transform: &Transform) -> CollisionShape {
+    let outer_radius = transform.scale.x * WAVE_MAX_RADIUS;
+    CollisionShape::Donut {
+        inner_radius: outer_radius - 20.,
+        outer_radius,
+    }
+}
+
 pub fn waves_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,